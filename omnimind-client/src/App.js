@@ -4,6 +4,21 @@ import { ethers } from 'ethers';
 import './App.css';
 import { mindCoinAddress, mindCoinABI } from './contractInfo';
 
+// OmniMind Core reports failed commands with a non-2xx HTTP status but still sends a
+// CommandResponse body ({ status, code, message, data }), so read it either way.
+async function parseCoreResponse(response) {
+  let data = null;
+  try {
+    data = await response.json();
+  } catch (e) {
+    // Ignore if response is not JSON
+  }
+  if (data && data.status) {
+    return data;
+  }
+  throw new Error(`HTTP error! status: ${response.status}`);
+}
+
 function App() {
   const [userAccount, setUserAccount] = useState(null);
   const [mindCoinBalance, setMindCoinBalance] = useState("0");
@@ -235,19 +250,7 @@ function App() {
         body: JSON.stringify({ raw_command: coreCommand }),
       });
 
-      if (!response.ok) {
-        // Try to get error message from server if possible, or use status text
-        let errorMsg = `HTTP error! status: ${response.status}`;
-        try {
-          const errorData = await response.json();
-          errorMsg = errorData.message || errorMsg; // Assuming server sends JSON error with a message field
-        } catch (e) {
-          // Ignore if error response is not JSON
-        }
-        throw new Error(errorMsg);
-      }
-
-      const data = await response.json(); // This is our CommandResponse struct
+      const data = await parseCoreResponse(response); // This is our CommandResponse struct
       setCoreResponse(data);
       
       if (data.status === "success") {
@@ -284,13 +287,7 @@ function App() {
         headers: { "Content-Type": "application/json" },
         body: JSON.stringify({ raw_command: rawCommand }),
       });
-      // ... (identical response handling logic as in sendCommandToCore) ...
-      if (!response.ok) {
-        let errorMsg = `HTTP error! status: ${response.status}`;
-        try { const errorData = await response.json(); errorMsg = errorData.message || errorMsg; } catch (e) { /* ignore */ }
-        throw new Error(errorMsg);
-      }
-      const data = await response.json();
+      const data = await parseCoreResponse(response);
       setCoreResponse(data);
       if (data.status === "success") {
           setFeedbackMessage("IPFS add command processed successfully.");
@@ -324,13 +321,7 @@ function App() {
         headers: { "Content-Type": "application/json" },
         body: JSON.stringify({ raw_command: rawCommand }),
       });
-      // ... (identical response handling logic as in sendCommandToCore) ...
-      if (!response.ok) {
-        let errorMsg = `HTTP error! status: ${response.status}`;
-        try { const errorData = await response.json(); errorMsg = errorData.message || errorMsg; } catch (e) { /* ignore */ }
-        throw new Error(errorMsg);
-      }
-      const data = await response.json();
+      const data = await parseCoreResponse(response);
       setCoreResponse(data);
       if (data.status === "success") {
           setFeedbackMessage("IPFS cat command processed successfully.");
@@ -472,6 +463,7 @@ function App() {
           <div id="core-response-area" style={{ marginTop: '15px', padding: '10px', border: '1px solid #ccc', whiteSpace: 'pre-wrap', textAlign: 'left', maxHeight: '300px', overflowY: 'auto' }}>
             <h4>Core Response:</h4>
             <p><strong>Status:</strong> {coreResponse.status}</p>
+            {coreResponse.code && <p><strong>Error code:</strong> {coreResponse.code}</p>}
             <p><strong>Message:</strong></p>
             <pre style={{ whiteSpace: 'pre-wrap', wordBreak: 'break-all' }}>{coreResponse.message}</pre>
             {coreResponse.data && (
@@ -0,0 +1,211 @@
+// src/api.rs for omnimind-core
+// Typed REST endpoints for programmatic clients. They share the command
+// implementations in commands.rs with the /command text endpoint, answer with
+// the same CommandResponse envelope and status codes, and are recorded like
+// commands (history and audit log, metrics, command_executed) under the session
+// "rest".
+use std::future::Future;
+use std::time::Instant;
+
+use actix_multipart::Multipart;
+use actix_web::error::JsonPayloadError;
+use actix_web::http::StatusCode;
+use actix_web::{guard, web, HttpMessage, HttpRequest, HttpResponse};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::{self, Principal};
+use crate::commands;
+use crate::history::Resolution;
+use crate::error::{self, CommandResult, OmniError};
+use crate::limits;
+use crate::progress::Progress;
+use crate::response::CommandResponse;
+
+// CreateNoteRequest: Body of POST /notes
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateNoteRequest {
+    /// Note title; its sanitized form becomes the note id.
+    #[schema(example = "Shopping list")]
+    pub title: String,
+    /// Note body, written after the header. Defaults to a placeholder line.
+    pub content: Option<String>,
+    /// Replace an existing note with the same id instead of failing with 409.
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+// FsQuery: Query string of GET /fs
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FsQuery {
+    /// Directory to list (default: current directory).
+    pub path: Option<String>,
+}
+
+// UploadForm: Multipart body of POST /ipfs/add. Only describes the body for the
+// OpenAPI document; the handler streams the field itself (see read_upload).
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct UploadForm {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+// Session recorded for REST calls.
+const REST_SESSION: &str = "rest";
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(json_error_handler))
+        .service(auth::scoped("/notes", "notes:read").guard(guard::Get()).route(web::get().to(list_notes)))
+        .service(auth::scoped("/notes", "notes:write").guard(guard::Post()).route(web::post().to(create_note)))
+        .service(auth::scoped("/notes/{id}", "notes:read").route(web::get().to(get_note)))
+        .service(auth::scoped("/fs", "fs:read").route(web::get().to(list_fs)))
+        .service(auth::scoped("/ipfs/add", "ipfs:add").route(web::post().to(ipfs_add)))
+        .service(auth::scoped("/ipfs/{cid}", "ipfs:read").route(web::get().to(ipfs_cat)));
+}
+
+// Sends a command result with the status its outcome maps to.
+pub fn respond(result: CommandResult) -> HttpResponse {
+    respond_with(result, StatusCode::OK)
+}
+
+// Like `respond`, with a custom status for success (e.g. 201 for created resources).
+fn respond_with(result: CommandResult, success_status: StatusCode) -> HttpResponse {
+    let response = error::into_response(result);
+    let status = if response.http_status().is_success() { success_status } else { response.http_status() };
+    HttpResponse::build(status).json(response)
+}
+
+// Runs a REST action and records it as the equivalent `command`, with the
+// request line ("GET /fs?path=/tmp") as what was sent.
+async fn recorded(req: &HttpRequest, command: &str, arguments: &[String], action: impl Future<Output = CommandResult>) -> CommandResult {
+    let principal = req.extensions().get::<Principal>().cloned().unwrap_or_else(Principal::anonymous);
+    let started = Instant::now();
+    let response = error::into_response(action.await);
+    let mut resolution = Resolution::default();
+    resolution.command(command, arguments, "rest");
+    crate::record_command_outcome(REST_SESSION, &principal, &format!("{} {}", req.method(), req.uri()), resolution, &response, started.elapsed());
+    Ok(response)
+}
+
+// Malformed JSON bodies get the usual envelope instead of actix's plain-text 400.
+// A /command body past actix's size limit can only be an overlong raw_command.
+fn json_error_handler(err: JsonPayloadError, req: &HttpRequest) -> actix_web::Error {
+    let error = match &err {
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } if req.path() == "/command" => OmniError::CommandTooLong(format!("Request body is too large: {}", err)),
+        _ => OmniError::InvalidArguments(format!("Invalid JSON body: {}", err)),
+    };
+    let response = respond(Err(error));
+    actix_web::error::InternalError::from_response(err, response).into()
+}
+
+// --- Handlers ---
+
+#[utoipa::path(
+    get, path = "/notes", tag = "notes",
+    responses(
+        (status = 200, description = "Notes with id, title, path, size and modified time", body = CommandResponse),
+        (status = 500, description = "The notes directory could not be read", body = CommandResponse),
+    )
+)]
+pub(crate) async fn list_notes(req: HttpRequest) -> HttpResponse {
+    respond(recorded(&req, "list_notes", &[], async { commands::list_notes_for_api() }).await)
+}
+
+#[utoipa::path(
+    post, path = "/notes", tag = "notes",
+    request_body = CreateNoteRequest,
+    responses(
+        (status = 201, description = "Note created; data holds its id and path", body = CommandResponse),
+        (status = 400, description = "Malformed body or a title with no usable characters", body = CommandResponse),
+        (status = 409, description = "A note with the same id exists and overwrite is not set (note_exists)", body = CommandResponse),
+        (status = 500, description = "The note file could not be written", body = CommandResponse),
+    )
+)]
+pub(crate) async fn create_note(req: HttpRequest, body: web::Json<CreateNoteRequest>) -> HttpResponse {
+    let result = recorded(&req, "create_note", std::slice::from_ref(&body.title), async { commands::create_note_for_api(&body.title, body.content.as_deref(), body.overwrite) }).await;
+    respond_with(result, StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get, path = "/notes/{id}", tag = "notes",
+    params(("id" = String, Path, description = "Note id as listed by GET /notes")),
+    responses(
+        (status = 200, description = "Note id, title, path and content", body = CommandResponse),
+        (status = 400, description = "Not a valid note id", body = CommandResponse),
+        (status = 404, description = "No such note", body = CommandResponse),
+    )
+)]
+pub(crate) async fn get_note(req: HttpRequest, id: web::Path<String>) -> HttpResponse {
+    respond(recorded(&req, "read_note", std::slice::from_ref(&id), async { commands::read_note_for_api(&id) }).await)
+}
+
+#[utoipa::path(
+    get, path = "/fs", tag = "filesystem",
+    params(FsQuery),
+    responses(
+        (status = 200, description = "Directory entries with name and type", body = CommandResponse),
+        (status = 400, description = "The path is not a directory", body = CommandResponse),
+        (status = 404, description = "The path does not exist", body = CommandResponse),
+    )
+)]
+pub(crate) async fn list_fs(req: HttpRequest, query: web::Query<FsQuery>) -> HttpResponse {
+    let path = query.path.as_deref().unwrap_or(".");
+    respond(recorded(&req, "ls", &[path.to_string()], async { commands::list_directory_contents_for_api(path) }).await)
+}
+
+#[utoipa::path(
+    post, path = "/ipfs/add", tag = "ipfs",
+    request_body(content = UploadForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "File added; data holds fileName, cid and size", body = CommandResponse),
+        (status = 400, description = "No file in the multipart body", body = CommandResponse),
+        (status = 413, description = "The file is larger than limits.max_upload_bytes", body = CommandResponse),
+        (status = 502, description = "The IPFS daemon is unreachable or failed", body = CommandResponse),
+    )
+)]
+pub(crate) async fn ipfs_add(req: HttpRequest, payload: Multipart) -> HttpResponse {
+    let upload = async {
+        let (file_name, bytes) = read_upload(payload).await?;
+        commands::add_bytes_to_ipfs_for_api(file_name, bytes.into(), &Progress::none()).await
+    };
+    respond(recorded(&req, "ipfs_add", &[], upload).await)
+}
+
+#[utoipa::path(
+    get, path = "/ipfs/{cid}", tag = "ipfs",
+    params(("cid" = String, Path, description = "Content identifier (Qm... or ba...)")),
+    responses(
+        (status = 200, description = "Content for the CID", body = CommandResponse),
+        (status = 400, description = "Not a valid CID", body = CommandResponse),
+        (status = 413, description = "The content is larger than limits.max_upload_bytes", body = CommandResponse),
+        (status = 502, description = "The IPFS daemon is unreachable or failed", body = CommandResponse),
+    )
+)]
+pub(crate) async fn ipfs_cat(req: HttpRequest, cid: web::Path<String>) -> HttpResponse {
+    respond(recorded(&req, "ipfs_cat", std::slice::from_ref(&cid), commands::cat_file_from_ipfs_for_api(&cid, &Progress::none())).await)
+}
+
+// Reads the "file" field of a multipart upload (or the first field that carries a filename).
+async fn read_upload(mut payload: Multipart) -> Result<(String, Vec<u8>), OmniError> {
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| OmniError::InvalidArguments(format!("Invalid multipart body: {}", e)))?;
+        let file_name = field.content_disposition().and_then(|cd| cd.get_filename()).map(String::from);
+        if field.name() != Some("file") && file_name.is_none() {
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| OmniError::InvalidArguments(format!("Error reading upload: {}", e)))?;
+            // Checked as it arrives, so an oversized upload is never held in memory.
+            limits::check_upload_size((bytes.len() + chunk.len()) as u64)?;
+            bytes.extend_from_slice(&chunk);
+        }
+        return Ok((file_name.unwrap_or_else(|| "upload".to_string()), bytes));
+    }
+    Err(OmniError::InvalidArguments("Usage: POST /ipfs/add with a multipart body containing a 'file' field.".to_string()))
+}
+
@@ -0,0 +1,419 @@
+// src/auth.rs for omnimind-core
+// API tokens and scopes. Tokens are created with `omnimind-core token create`,
+// stored only as SHA-256 hashes, and checked by the `require_token` middleware;
+// a running server picks up created and revoked tokens from the file.
+// Each token carries scopes per command family (fs:read, notes:write, ...): REST
+// resources need the scope they are registered with (see `scoped`), /command and
+// /ws the scope of every command they end up running (see CommandSpec::scope).
+use std::collections::HashMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceFactory, ServiceRequest, ServiceResponse};
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{web, HttpMessage, Resource};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api;
+use crate::error::OmniError;
+use crate::limits;
+use crate::AppState;
+
+// Every scope a token can be granted. "<family>:*" and "*" grant several at once.
+pub const SCOPES: &[(&str, &str)] = &[
+    ("fs:read", "List local directories (ls, GET /fs)"),
+    ("notes:read", "List and read notes (GET /notes)"),
+    ("notes:write", "Create notes (create_note, POST /notes)"),
+    ("ipfs:read", "Query the IPFS node and fetch content (ipfs_id, ipfs_cat, GET /ipfs/{cid})"),
+    ("ipfs:add", "Add local files or uploads to IPFS (ipfs_add, POST /ipfs/add)"),
+    ("nlu:admin", "Teach phrases, manage corrections and the NLU cache"),
+    ("events:read", "Subscribe to GET /events"),
+    ("history:read", "Read the command history and audit log (history)"),
+    ("metrics:read", "Scrape Prometheus metrics (GET /metrics) and see /readyz details"),
+];
+
+const TOKEN_PREFIX: &str = "omni_";
+
+// Principal: Who is making a request, and what they may do
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub name: String, // Token name, wallet address or "anonymous"
+    pub wallet: Option<String>, // EIP-55 address for Sign-In with Ethereum sessions
+    chain_id: Option<u64>,
+    scopes: Vec<String>,
+}
+
+impl Principal {
+    // Used when no tokens exist: auth is off and everything is allowed.
+    pub fn anonymous() -> Self {
+        Principal { name: "anonymous".to_string(), wallet: None, chain_id: None, scopes: vec!["*".to_string()] }
+    }
+
+    // A caller without a token on a public route while authentication is enforced.
+    pub fn unauthenticated() -> Self {
+        Principal { name: "anonymous".to_string(), wallet: None, chain_id: None, scopes: Vec::new() }
+    }
+
+    // The terminal user of `repl`, who can read the files directly anyway.
+    pub fn local() -> Self {
+        Principal { name: "local".to_string(), wallet: None, chain_id: None, scopes: vec!["*".to_string()] }
+    }
+
+    pub fn wallet(address: &str, chain_id: u64, scopes: Vec<String>) -> Self {
+        Principal { name: address.to_string(), wallet: Some(address.to_string()), chain_id: Some(chain_id), scopes }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "name": self.name, "wallet": self.wallet, "chain_id": self.chain_id, "scopes": self.scopes })
+    }
+
+    pub fn allows(&self, scope: &str) -> bool {
+        let family = scope.split(':').next().unwrap_or(scope);
+        self.scopes.iter().any(|granted| granted == "*" || granted == scope || granted.strip_suffix(":*") == Some(family))
+    }
+
+    // Ok if `scope` is None (nothing required) or granted.
+    pub fn require(&self, scope: Option<&str>) -> Result<(), OmniError> {
+        match scope {
+            Some(scope) if !self.allows(scope) => Err(OmniError::Forbidden { token: self.name.clone(), scope: scope.to_string() }),
+            _ => Ok(()),
+        }
+    }
+}
+
+// TokenRecord: One issued token; the token itself is never stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRecord {
+    pub name: String,
+    pub hash: String, // Hex SHA-256 of the token
+    pub scopes: Vec<String>,
+    pub created: String,
+}
+
+// TokenFile: The tokens as last read, and the file's (mtime, length) at the time
+struct TokenFile {
+    entries: Vec<TokenRecord>,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+pub struct TokenStore {
+    path: PathBuf,
+    file: Mutex<TokenFile>,
+}
+
+impl TokenStore {
+    // A missing file means no tokens; an unreadable one is an error, since
+    // ignoring it would silently turn authentication off.
+    pub fn load(path: PathBuf) -> Result<Self, OmniError> {
+        let file = read_tokens(&path)?;
+        Ok(TokenStore { path, file: Mutex::new(file) })
+    }
+
+    // Authentication is enforced as soon as one token exists.
+    pub fn is_enabled(&self) -> bool {
+        self.current().map(|file| !file.entries.is_empty()).unwrap_or(true)
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<Principal> {
+        let hash = hash_token(token);
+        let file = self.current().ok()?;
+        file.entries.iter().find(|record| record.hash == hash).map(|record| Principal { name: record.name.clone(), wallet: None, chain_id: None, scopes: record.scopes.clone() })
+    }
+
+    // The tokens, re-read first if the file changed since (`token create` and
+    // `token revoke` run in their own process), so a running server applies them
+    // on the next request. A file that cannot be read, e.g. while it is being
+    // written, keeps the tokens read before and is tried again next time.
+    fn current(&self) -> Result<MutexGuard<'_, TokenFile>, OmniError> {
+        let mut file = self.file.lock().map_err(|_| OmniError::Internal("Token store lock error".to_string()))?;
+        if file_stamp(&self.path) != file.stamp {
+            match read_tokens(&self.path) {
+                Ok(fresh) => {
+                    tracing::info!("Reloaded API tokens from '{}' ({} token(s)).", self.path.display(), fresh.entries.len());
+                    *file = fresh;
+                }
+                Err(e) => tracing::warn!("Keeping the previous API tokens: {}", e),
+            }
+        }
+        Ok(file)
+    }
+
+    // Issues a new token and returns it; this is the only time it is visible.
+    pub fn create(&self, name: &str, scopes: &[String]) -> Result<String, OmniError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(OmniError::InvalidArguments("Token name cannot be empty.".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(OmniError::InvalidArguments("Give at least one scope (see `token scopes`).".to_string()));
+        }
+        if let Some(unknown) = scopes.iter().find(|scope| !is_known_scope(scope)) {
+            return Err(OmniError::InvalidArguments(format!("Unknown scope '{}'. Run `token scopes` for the list.", unknown)));
+        }
+
+        let mut file = self.current()?;
+        if file.entries.iter().any(|record| record.name == name) {
+            return Err(OmniError::InvalidArguments(format!("A token named '{}' already exists; revoke it first.", name)));
+        }
+        let token = format!("{}{}", TOKEN_PREFIX, hex::encode(rand::random::<[u8; 32]>()));
+        file.entries.push(TokenRecord {
+            name: name.to_string(),
+            hash: hash_token(&token),
+            scopes: scopes.to_vec(),
+            created: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        self.save(&mut file)?;
+        Ok(token)
+    }
+
+    pub fn revoke(&self, name: &str) -> Result<bool, OmniError> {
+        let mut file = self.current()?;
+        let before = file.entries.len();
+        file.entries.retain(|record| record.name != name);
+        if file.entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&mut file)?;
+        Ok(true)
+    }
+
+    pub fn list(&self) -> Vec<TokenRecord> {
+        self.current().map(|file| file.entries.clone()).unwrap_or_default()
+    }
+
+    // Writes a temp file and renames it into place, so a server reading the file
+    // meanwhile sees either the old tokens or the new ones.
+    fn save(&self, file: &mut TokenFile) -> Result<(), OmniError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| OmniError::io(format!("Failed to create '{}'", parent.display()), e))?;
+        }
+        let json = serde_json::to_string_pretty(&file.entries).map_err(|e| OmniError::Internal(format!("Failed to serialize tokens: {}", e)))?;
+        let tmp_path = self.path.with_extension("tmp");
+        write_private(&tmp_path, &json)
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .map_err(|e| OmniError::io(format!("Failed to write tokens file '{}'", self.path.display()), e))?;
+        file.stamp = file_stamp(&self.path);
+        Ok(())
+    }
+}
+
+fn read_tokens(path: &Path) -> Result<TokenFile, OmniError> {
+    let stamp = file_stamp(path);
+    let entries = match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str::<Vec<TokenRecord>>(&contents)
+            .map_err(|e| OmniError::Config(format!("tokens file '{}' is not valid: {}", path.display(), e)))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(OmniError::io(format!("Failed to read tokens file '{}'", path.display()), e)),
+    };
+    Ok(TokenFile { entries, stamp })
+}
+
+// The length is compared too, since two writes can share a modification time.
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn is_known_scope(scope: &str) -> bool {
+    scope == "*"
+        || SCOPES.iter().any(|(known, _)| *known == scope)
+        || scope.strip_suffix(":*").is_some_and(|family| SCOPES.iter().any(|(known, _)| known.starts_with(&format!("{}:", family))))
+}
+
+// The tokens file is only readable by its owner.
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    file.write_all(contents.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    fs::write(path, contents)
+}
+
+// --- Middleware ---
+
+// Who a request comes from, for rate limits and /command sessions: the token (or
+// wallet) it authenticated with, or else the address it came from. Unix socket
+// peers count as one caller.
+pub fn caller_key(principal: &Principal, peer: Option<SocketAddr>) -> String {
+    if principal.name != "anonymous" {
+        return format!("caller:{}", principal.name);
+    }
+    match peer {
+        Some(peer) => format!("ip:{}", peer.ip()),
+        None => "unix".to_string(),
+    }
+}
+
+// Routes anyone may call. Matched exactly on the raw path: a percent-encoded
+// spelling of one of these is not public and needs a token like any other route.
+const PUBLIC_PATHS: &[&str] = &["/openapi.json", "/auth/nonce", "/auth/verify", "/healthz", "/readyz"];
+
+// Reads "Authorization: Bearer <token>". Browser WebSocket clients cannot set
+// headers, so the /ws upgrade also takes ?access_token=<token>; nowhere else,
+// since query strings end up in access logs and proxies.
+fn bearer_token(req: &ServiceRequest) -> Option<String> {
+    if let Some(header) = req.headers().get(actix_web::http::header::AUTHORIZATION) {
+        return header.to_str().ok()?.strip_prefix("Bearer ").map(|token| token.trim().to_string());
+    }
+    if req.path() != "/ws" {
+        return None;
+    }
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).ok()?;
+    query.get("access_token").cloned()
+}
+
+// Answers 401 without a valid token (except on PUBLIC_PATHS); otherwise stores the
+// caller's Principal in the request extensions for the handler. API tokens and
+// wallet session tokens (see siwe.rs) are accepted alike; a valid one identifies
+// the caller even while authentication is not enforced. Scopes are checked after
+// routing, by the resource (see `scoped`).
+pub async fn require_token(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let (authenticated, enforced) = match req.app_data::<web::Data<AppState>>() {
+        Some(app_state) => {
+            let authenticated = bearer_token(&req).and_then(|token| app_state.tokens.authenticate(&token).or_else(|| app_state.siwe.authenticate(&token)));
+            (authenticated, app_state.tokens.is_enabled())
+        }
+        None => (None, false),
+    };
+    let principal = match authenticated {
+        Some(principal) => principal,
+        None if enforced && !PUBLIC_PATHS.contains(&req.path()) => {
+            // These never reach limits::rate_limit, so charge the address here.
+            if let Err(error) = limits::check_rate(&caller_key(&Principal::unauthenticated(), req.peer_addr())) {
+                return Ok(req.into_response(limits::rate_limited(error)));
+            }
+            let error = OmniError::Unauthorized("Missing or invalid API token. Send 'Authorization: Bearer <token>'.".to_string());
+            return Ok(req.into_response(api::respond(Err(error))));
+        }
+        None if enforced => Principal::unauthenticated(),
+        None => Principal::anonymous(),
+    };
+    req.extensions_mut().insert(principal);
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+// A resource whose routes all need `scope`. The check runs once the router has
+// picked the resource, so it follows the decoded path actually served, however
+// the request spelled it.
+pub fn scoped(path: &str, scope: &'static str) -> Resource<impl ServiceFactory<ServiceRequest, Config = (), Response = ServiceResponse<BoxBody>, Error = actix_web::Error, InitError = ()>> {
+    web::resource(path).wrap(from_fn(move |req, next| require_scope(scope, req, next)))
+}
+
+// Answers 403 unless the Principal stored by `require_token` holds `scope`.
+async fn require_scope(scope: &'static str, req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let principal = req.extensions().get::<Principal>().cloned();
+    let allowed = match principal {
+        Some(principal) => principal.require(Some(scope)),
+        None => Err(OmniError::Unauthorized("Missing or invalid API token. Send 'Authorization: Bearer <token>'.".to_string())),
+    };
+    if let Err(error) = allowed {
+        return Ok(req.into_response(api::respond(Err(error))));
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::{test, App};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::testing::ConfigGuard;
+    use crate::config::Config;
+
+    // Fixture: App state with one API token, kept in a scratch directory that is
+    // removed on drop. REST calls are recorded through the live config, so it is
+    // installed for the test's duration with history turned off.
+    struct Fixture {
+        app_state: web::Data<AppState>,
+        token: String,
+        dir: TempDir,
+        _config: ConfigGuard,
+    }
+
+    async fn app_state_with_token(scopes: &[&str]) -> Fixture {
+        let dir = TempDir::new().expect("scratch directory");
+        let mut config = Config::default();
+        config.nlu.backend = "none".to_string();
+        config.storage.tokens_file = dir.path().join("tokens.json");
+        config.storage.corrections_file = dir.path().join("corrections.json");
+        config.history.enabled = false;
+        let guard = crate::config::testing::install(config.clone()).await;
+        let app_state = crate::build_app_state(&config).expect("app state");
+        let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+        let token = app_state.tokens.create("test", &scopes).expect("token");
+        Fixture { app_state: web::Data::new(app_state), token, dir, _config: guard }
+    }
+
+    async fn status_of(app_state: &web::Data<AppState>, token: &str, method: Method, uri: &str) -> StatusCode {
+        let app = test::init_service(App::new().app_data(app_state.clone()).wrap(from_fn(require_token)).configure(crate::configure_routes)).await;
+        let request = test::TestRequest::default().method(method).uri(uri).insert_header((AUTHORIZATION, format!("Bearer {}", token))).to_request();
+        test::call_service(&app, request).await.status()
+    }
+
+    async fn status_with_query_token(app_state: &web::Data<AppState>, uri: &str) -> StatusCode {
+        let app = test::init_service(App::new().app_data(app_state.clone()).wrap(from_fn(require_token)).configure(crate::configure_routes)).await;
+        test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await.status()
+    }
+
+    #[actix_web::test]
+    async fn a_token_revoked_by_another_process_is_rejected_on_the_next_request() {
+        let Fixture { app_state, token, dir, .. } = &app_state_with_token(&["notes:read"]).await;
+        assert_eq!(status_of(app_state, token, Method::GET, "/notes").await, StatusCode::OK);
+        // `token revoke` loads its own store from the same file.
+        let cli_store = TokenStore::load(dir.path().join("tokens.json")).unwrap();
+        let other_token = cli_store.create("other", &["notes:read".to_string()]).unwrap();
+        assert_eq!(status_of(app_state, &other_token, Method::GET, "/notes").await, StatusCode::OK);
+        assert!(cli_store.revoke("test").unwrap());
+        assert_eq!(status_of(app_state, token, Method::GET, "/notes").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn query_tokens_are_only_accepted_on_the_websocket_upgrade() {
+        let Fixture { app_state, token, .. } = &app_state_with_token(&["notes:read", "events:read"]).await;
+        assert_eq!(status_with_query_token(app_state, &format!("/notes?access_token={}", token)).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_with_query_token(app_state, &format!("/events?access_token={}", token)).await, StatusCode::UNAUTHORIZED);
+        // Authenticated, then refused for not being a WebSocket handshake.
+        assert_eq!(status_with_query_token(app_state, &format!("/ws?access_token={}", token)).await, StatusCode::BAD_REQUEST);
+        assert_eq!(status_with_query_token(app_state, "/ws?access_token=omni_wrong").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn percent_encoded_paths_need_the_scope_of_the_route_they_reach() {
+        let Fixture { app_state, token, .. } = &app_state_with_token(&["events:read", "ipfs:read"]).await;
+        for (method, uri) in [
+            (Method::GET, "/fs?path=/"),
+            (Method::GET, "/%66s?path=/"),
+            (Method::GET, "/%6Eotes"),
+            (Method::GET, "/%6Eotes/groceries"),
+            (Method::POST, "/%6Eotes"),
+            (Method::POST, "/ipfs/%61dd"),
+        ] {
+            assert_eq!(status_of(app_state, token, method.clone(), uri).await, StatusCode::FORBIDDEN, "{} {}", method, uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn encoded_paths_are_served_with_the_scope() {
+        let Fixture { app_state, token, .. } = &app_state_with_token(&["fs:read"]).await;
+        assert_eq!(status_of(app_state, token, Method::GET, "/%66s?path=.").await, StatusCode::OK);
+        assert_eq!(status_of(app_state, token, Method::GET, "/%6Eotes").await, StatusCode::FORBIDDEN);
+    }
+}
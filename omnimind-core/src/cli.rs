@@ -0,0 +1,231 @@
+// src/cli.rs for omnimind-core
+// Command-line interface. With no subcommand the binary starts the API server;
+// `repl`, `run` and `script` run commands in-process, `client` sends them to a
+// running server, and the other subcommands manage
+// local state (API tokens) or show the configuration without starting anything. The global flags override omnimind.toml and OMNIMIND_* variables.
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::auth::{self, TokenStore};
+use crate::config::{self, Sources};
+use crate::error::OmniError;
+
+#[derive(Debug, Parser)]
+#[command(name = "omnimind-core", version, about = "OmniMind Core API server")]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigFlags,
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+// ConfigFlags: Command-line overrides, the last configuration layer
+#[derive(Debug, Args)]
+pub struct ConfigFlags {
+    /// Config file to read instead of ./omnimind.toml (or OMNIMIND_CONFIG)
+    #[arg(short = 'c', long = "config", value_name = "FILE", global = true)]
+    pub config_file: Option<PathBuf>,
+    /// Address to listen on (server.bind), or "none" for the Unix socket only
+    #[arg(long, value_name = "HOST:PORT", global = true)]
+    bind: Option<String>,
+    /// NLU backend: python, http, rules or none (nlu.backend)
+    #[arg(long, value_name = "BACKEND", global = true)]
+    nlu_backend: Option<String>,
+    /// Directory notes are stored in (storage.notes_dir)
+    #[arg(long, value_name = "DIR", global = true)]
+    notes_dir: Option<String>,
+    /// IPFS HTTP API base URL (ipfs.api_url)
+    #[arg(long, value_name = "URL", global = true)]
+    ipfs_url: Option<String>,
+    /// Set any config key, e.g. --set nlu.confidence_threshold=0.7 (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    set: Vec<String>,
+}
+
+impl ConfigFlags {
+    // (key, value) pairs in the order they apply; --set wins over the named flags.
+    pub fn overrides(&self) -> Vec<(String, String)> {
+        let named = [("server.bind", &self.bind), ("nlu.backend", &self.nlu_backend), ("storage.notes_dir", &self.notes_dir), ("ipfs.api_url", &self.ipfs_url)];
+        let mut overrides: Vec<(String, String)> = named.into_iter().filter_map(|(key, value)| value.as_ref().map(|value| (key.to_string(), value.clone()))).collect();
+        for assignment in &self.set {
+            match assignment.split_once('=') {
+                Some((key, value)) => overrides.push((key.trim().to_string(), value.to_string())),
+                // Left for config::load to reject with the list of known keys.
+                None => overrides.push((assignment.trim().to_string(), String::new())),
+            }
+        }
+        overrides
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Start the API server (the default)
+    Serve,
+    /// Interactive shell that runs commands in-process, without the API server
+    Repl,
+    /// Run one command in-process and exit with its status (see README for exit codes)
+    Run {
+        /// The command line, e.g. "create_note Groceries" (quotes are optional)
+        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+        output: OutputFormat,
+    },
+    /// Run a script of commands, one per line (`-` reads standard input)
+    Script {
+        file: PathBuf,
+        /// Set a script variable, taking precedence over its `set` line (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+        /// Keep running after a line fails (the exit code is still the first failure's)
+        #[arg(long)]
+        keep_going: bool,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+        output: OutputFormat,
+    },
+    /// Send commands to a running server, sharing its state, NLU process and session
+    Client {
+        /// Server URL (default: from server.bind, https when TLS is configured)
+        #[arg(long, env = "OMNIMIND_URL", value_name = "URL")]
+        url: Option<String>,
+        /// API token to send as "Authorization: Bearer ..."
+        #[arg(long, env = "OMNIMIND_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        #[command(subcommand)]
+        action: ClientAction,
+    },
+    /// Manage API tokens (stored hashed in storage.tokens_file)
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ClientAction {
+    /// Send one command over POST /command and exit with its status
+    Run {
+        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+        output: OutputFormat,
+    },
+    /// Interactive shell on the server's shared session, with live progress
+    Repl,
+    /// Run a script of commands on the server (`-` reads standard input)
+    Script {
+        file: PathBuf,
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+        #[arg(long)]
+        keep_going: bool,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+        output: OutputFormat,
+    },
+}
+
+// OutputFormat: How `run` and `script` print results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Messages and tables
+    Human,
+    /// The CommandResponse JSON (one object per line for scripts)
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective configuration and where each value came from
+    Show,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TokenAction {
+    /// Create a token and print it once
+    Create {
+        /// Name to identify the token by, e.g. "react-client"
+        name: String,
+        /// Comma-separated scopes, e.g. notes:read,notes:write or ipfs:* or *
+        #[arg(long, value_delimiter = ',', required = true)]
+        scopes: Vec<String>,
+    },
+    /// List token names, scopes and creation times
+    List,
+    /// Revoke a token by name
+    Revoke { name: String },
+    /// List the available scopes
+    Scopes,
+}
+
+// Runs a `token` subcommand; returns the process exit code.
+pub fn run_token_command(action: TokenAction) -> i32 {
+    if let TokenAction::Scopes = action {
+        for (scope, description) in auth::SCOPES {
+            println!("{:<12} {}", scope, description);
+        }
+        println!("{:<12} Every scope of a family, e.g. notes:*", "<family>:*");
+        println!("{:<12} Everything", "*");
+        return 0;
+    }
+
+    let path = config::current().storage.tokens_file.clone();
+    let store = match TokenStore::load(path.clone()) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 1;
+        }
+    };
+    let result = match action {
+        TokenAction::Create { name, scopes } => store.create(&name, &scopes).map(|token| {
+            println!("✅ Created token '{}' with scopes {}.", name.trim(), scopes.join(","));
+            println!("{}", token);
+            println!("Store it now: it is only kept hashed in '{}' and cannot be shown again.", path.display());
+            println!("A running server accepts it from its next request on.");
+        }),
+        TokenAction::List => {
+            let tokens = store.list();
+            if tokens.is_empty() {
+                println!("No API tokens: authentication is disabled. Create one with `omnimind-core token create <name> --scopes ...`.");
+            }
+            for record in tokens {
+                println!("{:<20} {:<40} created {}", record.name, record.scopes.join(","), record.created);
+            }
+            Ok(())
+        }
+        TokenAction::Revoke { name } => match store.revoke(&name) {
+            Ok(true) => {
+                println!("✅ Revoked token '{}'. A running server rejects it from its next request on.", name);
+                Ok(())
+            }
+            Ok(false) => Err(OmniError::NotFound(format!("No token named '{}'.", name))),
+            Err(e) => Err(e),
+        },
+        TokenAction::Scopes => Ok(()),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            1
+        }
+    }
+}
+
+// Runs a `config` subcommand; returns the process exit code. Loading already
+// validated the configuration, so reaching this point means it is usable.
+pub fn run_config_command(action: ConfigAction, sources: &Sources) -> i32 {
+    match action {
+        ConfigAction::Show => {
+            println!("{}", config::render(&config::current(), sources));
+            0
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// src/client.rs for omnimind-core
+// `omnimind-core client`: sends commands to a running server instead of running
+// them in-process, so the terminal shares the server's state, NLU process and
+// session with the React client. `client run` posts to /command; `client repl`
+// and `client script` keep a /ws connection open on the shared session
+// (?session=shared) so progress is streamed while commands run.
+use actix_web::web;
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::{HeaderValue, AUTHORIZATION};
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::cli::{ClientAction, OutputFormat};
+use crate::config::{self, ServerConfig};
+use crate::error::{self, CommandResult, OmniError};
+use crate::progress::ProgressEvent;
+use crate::response::CommandResponse;
+use crate::{repl, script, server, AppState, Session};
+
+// Server: A running core and the API token to present to it
+#[derive(Debug, Clone)]
+pub struct Server {
+    url: String, // http(s)://host:port, without a trailing slash
+    token: Option<String>,
+}
+
+impl Server {
+    // Without --url, the server this configuration starts (server.bind and TLS).
+    pub fn new(url: Option<String>, token: Option<String>) -> Result<Self, OmniError> {
+        let url = match url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => default_url(&config::current().server)?,
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(OmniError::InvalidArguments(format!("Server URL '{}' must start with http:// or https://.", url)));
+        }
+        Ok(Server { url, token: token.filter(|token| !token.trim().is_empty()) })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    // One command over POST /command, in the session plain HTTP clients share.
+    async fn post_command(&self, command: &str) -> CommandResult {
+        let mut request = reqwest::Client::new().post(format!("{}/command", self.url)).json(&serde_json::json!({ "raw_command": command }));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| self.unreachable(e))?;
+        let status = response.status();
+        // Errors come back in the same envelope, with their own status and code.
+        response.json::<CommandResponse>().await.map_err(|e| OmniError::Internal(format!("Unexpected answer from {} (HTTP {}): {}", self.url, status, e)))
+    }
+
+    fn unreachable(&self, reason: impl ToString) -> OmniError {
+        OmniError::ServerUnreachable { url: self.url.clone(), reason: reason.to_string() }
+    }
+}
+
+// A wildcard bind address is reached over loopback.
+fn default_url(config: &ServerConfig) -> Result<String, OmniError> {
+    let address = server::tcp_address(config).ok_or_else(|| OmniError::InvalidArguments("server.bind is \"none\" (Unix socket only); pass --url to reach the server over TCP.".to_string()))?;
+    let address = address.replacen("0.0.0.0:", "127.0.0.1:", 1).replacen("[::]:", "[::1]:", 1);
+    Ok(format!("{}://{}", if config.tls_cert.is_some() { "https" } else { "http" }, address))
+}
+
+// Runs a `client` subcommand; returns the process exit code.
+pub async fn run(url: Option<String>, token: Option<String>, action: ClientAction) -> i32 {
+    let server = match Server::new(url, token) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return e.code().exit_code();
+        }
+    };
+    match action {
+        ClientAction::Run { command, output } => run_command(&server, &command.join(" "), output).await,
+        ClientAction::Repl => repl::run(Target::Remote(server)).await,
+        ClientAction::Script { file, vars, keep_going, output } => script::run_script(&Target::Remote(server), &file, &vars, keep_going, output).await,
+    }
+}
+
+// Sends one command over HTTP; returns the process exit code like `run`.
+async fn run_command(server: &Server, command: &str, format: OutputFormat) -> i32 {
+    let response = error::into_response(server.post_command(command).await);
+    script::print_response(format, &response);
+    response.exit_code()
+}
+
+// --- Targets ---
+
+// Target: Where the terminal modes (repl.rs, script.rs) run their commands
+pub enum Target {
+    Local,
+    Remote(Server),
+}
+
+impl Target {
+    // Starts the in-process core, or opens a session on the server. `session` names
+    // the in-process session; on a server the shared session is joined instead.
+    pub async fn connect(&self, session: &str) -> Result<Runner, OmniError> {
+        match self {
+            Target::Local => {
+                let app_state = crate::build_app_state(&config::current())?;
+                Ok(Runner::Local { app_state: web::Data::new(app_state), session: Session::new(session) })
+            }
+            Target::Remote(server) => Connection::open(server).await.map(|connection| Runner::Remote(Box::new(connection))),
+        }
+    }
+}
+
+// Runner: A connected Target, ready to run commands
+pub enum Runner {
+    Local { app_state: web::Data<AppState>, session: Session },
+    Remote(Box<Connection>),
+}
+
+impl Runner {
+    // Runs one command, passing progress reports to `on_progress` as they arrive.
+    pub async fn run(&mut self, line: &str, on_progress: impl Fn(&ProgressEvent)) -> CommandResponse {
+        match self {
+            Runner::Local { app_state, session } => crate::run_local_command(line, app_state, session, on_progress).await,
+            Runner::Remote(connection) => error::into_response(connection.run(line, on_progress).await),
+        }
+    }
+
+    // Ends a server session with a close frame rather than a dropped connection.
+    pub async fn close(self) {
+        if let Runner::Remote(mut connection) = self {
+            let _ = connection.socket.close(None).await;
+            // Wait for the server's answering close frame before the process exits.
+            while let Some(Ok(_)) = connection.socket.next().await {}
+        }
+    }
+}
+
+// --- WebSocket ---
+
+// Connection: A /ws session on a running server
+pub struct Connection {
+    server: Server,
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+}
+
+impl Connection {
+    async fn open(server: &Server) -> Result<Self, OmniError> {
+        let url = format!("{}/ws?session=shared", server.url.replacen("http", "ws", 1));
+        let mut request = url.into_client_request().map_err(|e| OmniError::InvalidArguments(format!("Invalid server URL '{}': {}", server.url, e)))?;
+        if let Some(token) = &server.token {
+            let header = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|_| OmniError::InvalidArguments("The API token contains characters not allowed in a header.".to_string()))?;
+            request.headers_mut().insert(AUTHORIZATION, header);
+        }
+        let (socket, _) = tokio_tungstenite::connect_async(request).await.map_err(|e| match e {
+            // A rejected handshake carries the usual error envelope.
+            tungstenite::Error::Http(response) => {
+                let rejected = response.body().as_deref().and_then(|body| serde_json::from_slice::<CommandResponse>(body).ok());
+                let message = rejected.map_or_else(|| format!("The server refused the connection (HTTP {}).", response.status()), |rejected| rejected.message);
+                match response.status().as_u16() {
+                    401 => OmniError::Unauthorized(message),
+                    _ => OmniError::Internal(message),
+                }
+            }
+            other => server.unreachable(other),
+        })?;
+        Ok(Connection { server: server.clone(), socket, next_id: 0 })
+    }
+
+    // Sends one command and reads events until its result; the session greeting and
+    // "accepted"/"clarification" messages carry nothing the result does not.
+    async fn run(&mut self, line: &str, on_progress: impl Fn(&ProgressEvent)) -> CommandResult {
+        self.next_id += 1;
+        let id = serde_json::json!(self.next_id);
+        let message = serde_json::json!({ "id": id, "raw_command": line }).to_string();
+        self.socket.send(Message::text(message)).await.map_err(|e| self.server.unreachable(e))?;
+        while let Some(message) = self.socket.next().await {
+            let text = match message.map_err(|e| self.server.unreachable(e))? {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(mut event) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+            if event["id"] != id {
+                continue;
+            }
+            match event["type"].as_str() {
+                Some("progress") | Some("output") => {
+                    if let Ok(progress) = serde_json::from_value::<ProgressEvent>(event) {
+                        on_progress(&progress);
+                    }
+                }
+                Some("result") => {
+                    return serde_json::from_value(event["response"].take()).map_err(|e| OmniError::Internal(format!("Unexpected result from {}: {}", self.server.url, e)));
+                }
+                _ => {}
+            }
+        }
+        Err(self.server.unreachable("the connection was closed"))
+    }
+}
@@ -0,0 +1,276 @@
+// src/commands.rs for omnimind-core
+// OS and IPFS command implementations. Each returns a CommandResult; failures are
+// OmniErrors and become error responses in one place (see error.rs).
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures_util::StreamExt;
+use serde::{Serialize, Deserialize};
+
+use crate::config;
+use crate::error::{CommandResult, OmniError};
+use crate::events::{self, CoreEvent};
+use crate::limits;
+use crate::metrics;
+use crate::progress::Progress;
+use crate::response::CommandResponse;
+
+pub const NOTE_EXTENSION: &str = ".omni.txt";
+
+// Where notes live (storage.notes_dir).
+pub fn notes_dir() -> PathBuf {
+    config::current().storage.notes_dir.clone()
+}
+
+// Base URL of the IPFS HTTP API (ipfs.api_url), without a trailing slash.
+fn ipfs_api_url() -> String {
+    config::current().ipfs.api_url.trim_end_matches('/').to_string()
+}
+// Uploads are streamed to IPFS in chunks of this size so progress can be reported.
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+// IpfsIdResponse: The part of IPFS's /id answer we use
+#[derive(Debug, Serialize, Deserialize)]
+struct IpfsIdResponse {
+    #[serde(alias = "ID")]
+    id: String,
+}
+
+// IpfsAddResponse: IPFS's /add answer for a single file
+#[derive(Debug, Serialize, Deserialize)]
+struct IpfsAddResponse {
+    #[serde(alias = "Name")]
+    name: String,
+    #[serde(alias = "Hash")]
+    hash: String,
+    #[serde(alias = "Size")]
+    size: String,
+}
+
+// --- Filesystem ---
+
+pub fn list_directory_contents_for_api(dir_path_str: &str) -> CommandResult {
+    let path = Path::new(dir_path_str);
+    if !path.exists() {
+        return Err(OmniError::PathNotFound(dir_path_str.to_string()));
+    }
+    if !path.is_dir() {
+        return Err(OmniError::NotADirectory(dir_path_str.to_string()));
+    }
+
+    let entries = fs::read_dir(path).map_err(|e| OmniError::io(format!("Error reading directory '{}'", dir_path_str), e))?;
+    let mut entries_vec = Vec::new();
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        let file_name_str = entry.file_name().to_string_lossy().into_owned();
+        let entry_type = if entry_path.is_dir() { "Directory" } else if entry_path.is_file() { "File" } else { "Other" };
+        entries_vec.push(serde_json::json!({ "name": file_name_str, "type": entry_type }));
+    }
+    Ok(CommandResponse::success(format!("Contents of '{}':", dir_path_str), Some(serde_json::Value::Array(entries_vec))))
+}
+
+// Notes are identified by their sanitized title, which is also the file stem.
+fn note_id_from_title(title: &str) -> String {
+    title.chars().filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_').collect::<String>().replace(" ", "_")
+}
+
+// `content` replaces the "(Start your note here)" placeholder when given. An
+// existing note with the same id is replaced only if `overwrite` is set.
+pub fn create_note_for_api(title: &str, content: Option<&str>, overwrite: bool) -> CommandResult {
+    let notes_dir = notes_dir();
+    if !notes_dir.exists() {
+        fs::create_dir_all(&notes_dir).map_err(|e| OmniError::io(format!("Error creating notes directory '{}'", notes_dir.display()), e))?;
+    }
+
+    let sane_title = note_id_from_title(title);
+    if sane_title.is_empty() {
+        return Err(OmniError::InvalidNoteTitle);
+    }
+
+    let file_name = format!("{}{}", sane_title, NOTE_EXTENSION);
+    let file_path = notes_dir.join(&file_name);
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).create_new(!overwrite).open(&file_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => OmniError::NoteExists(sane_title.clone()),
+        _ => OmniError::io(format!("Error creating note file '{}'", file_path.display()), e),
+    })?;
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let body = content.unwrap_or("(Start your note here)\n");
+    let content = format!("OmniMind Note\nTitle: {}\nCreated: {}\n\n---\n{}", title, timestamp, body);
+    let data = Some(serde_json::json!({ "id": sane_title, "path": file_path.display().to_string() }));
+    // The note exists even if the header could not be written, so this still counts as created.
+    match file.write_all(content.as_bytes()) {
+        Ok(_) => Ok(CommandResponse::success(format!("Successfully created note: '{}'", file_path.display()), data)),
+        Err(e) => Ok(CommandResponse::success(format!("Created empty note: '{}', but failed to write content: {}", file_path.display(), e), data)),
+    }
+}
+
+// Lists the notes in the notes directory with their id, title (from the note header) and size.
+pub fn list_notes_for_api() -> CommandResult {
+    let notes_dir = notes_dir();
+    if !notes_dir.exists() {
+        return Ok(CommandResponse::success("0 note(s):", Some(serde_json::json!([]))));
+    }
+    let entries = fs::read_dir(&notes_dir).map_err(|e| OmniError::io(format!("Error reading notes directory '{}'", notes_dir.display()), e))?;
+
+    let mut notes = Vec::new();
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(id) = file_name.strip_suffix(NOTE_EXTENSION) else { continue };
+        let path = entry.path();
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let modified = entry.metadata().ok().and_then(|m| m.modified().ok()).map(|time| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M:%S").to_string());
+        notes.push(serde_json::json!({
+            "id": id,
+            "title": note_title(&contents).unwrap_or(id),
+            "path": path.display().to_string(),
+            "size": contents.len(),
+            "modified": modified,
+        }));
+    }
+    notes.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    Ok(CommandResponse::success(format!("{} note(s):", notes.len()), Some(serde_json::Value::Array(notes))))
+}
+
+pub fn read_note_for_api(id: &str) -> CommandResult {
+    // Ids are sanitized titles, so anything else (like "../secret") cannot name a note.
+    if id.is_empty() || note_id_from_title(id) != id {
+        return Err(OmniError::InvalidArguments(format!("Invalid note id '{}'.", id)));
+    }
+    let file_path = notes_dir().join(format!("{}{}", id, NOTE_EXTENSION));
+    if !file_path.is_file() {
+        return Err(OmniError::NotFound(format!("No note with id '{}'.", id)));
+    }
+    let content = fs::read_to_string(&file_path).map_err(|e| OmniError::io(format!("Error reading note '{}'", file_path.display()), e))?;
+    Ok(CommandResponse::success(
+        format!("Note '{}':", id),
+        Some(serde_json::json!({
+            "id": id,
+            "title": note_title(&content).unwrap_or(id),
+            "path": file_path.display().to_string(),
+            "content": content,
+        })),
+    ))
+}
+
+fn note_title(contents: &str) -> Option<&str> {
+    contents.lines().take(3).find_map(|line| line.strip_prefix("Title: "))
+}
+
+// --- IPFS ---
+
+// Sends a prepared request to the IPFS API and turns transport failures and
+// non-2xx answers (whose body usually carries a JSON "Message") into OmniErrors.
+async fn send_to_ipfs(request: reqwest::RequestBuilder, endpoint: &str) -> Result<reqwest::Response, OmniError> {
+    let started = Instant::now();
+    let sent = request.send().await;
+    metrics::record_ipfs_request(endpoint, sent.as_ref().is_ok_and(|response| response.status().is_success()), started.elapsed());
+    let response = sent.map_err(|source| OmniError::IpfsUnreachable { endpoint: endpoint.to_string(), source })?;
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status_code = response.status();
+    let error_body = response.text().await.unwrap_or_else(|e| format!("Failed to read error body: {}", e));
+    let error_message = serde_json::from_str::<serde_json::Value>(&error_body)
+        .ok()
+        .and_then(|json| json.get("Message").and_then(|v| v.as_str()).map(String::from))
+        .unwrap_or(error_body);
+    Err(OmniError::Ipfs(format!("IPFS API {} failed with {}: {}", endpoint, status_code, error_message)))
+}
+
+pub async fn get_ipfs_id_for_api() -> CommandResult {
+    let client = reqwest::Client::new();
+    let response = send_to_ipfs(client.post(format!("{}/id", ipfs_api_url())), "/id").await?;
+    let ipfs_id_data = response
+        .json::<IpfsIdResponse>()
+        .await
+        .map_err(|e| OmniError::Ipfs(format!("Failed to parse IPFS ID response: {}", e)))?;
+    Ok(CommandResponse::success("Successfully fetched IPFS Node ID.", Some(serde_json::json!({ "ipfsNodeId": ipfs_id_data.id }))))
+}
+
+pub async fn add_file_to_ipfs_for_api(local_file_path_str: &str, progress: &Progress) -> CommandResult {
+    let file_path = Path::new(local_file_path_str);
+    if !file_path.exists() {
+        return Err(OmniError::PathNotFound(local_file_path_str.to_string()));
+    }
+    if !file_path.is_file() {
+        return Err(OmniError::NotAFile(local_file_path_str.to_string()));
+    }
+    let metadata = fs::metadata(file_path).map_err(|e| OmniError::io(format!("Failed to read local file '{}'", local_file_path_str), e))?;
+    limits::check_upload_size(metadata.len())?;
+
+    progress.stage("ipfs_add", format!("Reading '{}'...", local_file_path_str));
+    let file_content_bytes = fs::read(file_path).map_err(|e| OmniError::io(format!("Failed to read local file '{}'", local_file_path_str), e))?;
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    add_bytes_to_ipfs_for_api(file_name, file_content_bytes.into(), progress).await
+}
+
+// Shared by ipfs_add (local files) and POST /ipfs/add (uploaded files).
+pub async fn add_bytes_to_ipfs_for_api(file_name: String, bytes: Bytes, progress: &Progress) -> CommandResult {
+    let total = bytes.len() as u64;
+    // Slices share the buffer, so the upload is held in memory once.
+    let chunks = (0..bytes.len()).step_by(UPLOAD_CHUNK_SIZE).map(move |start| bytes.slice(start..(start + UPLOAD_CHUNK_SIZE).min(bytes.len())));
+    let upload_progress = progress.clone();
+    let upload_name = file_name.clone();
+    let mut sent: u64 = 0;
+    let mut last_percent: Option<u64> = None;
+    // Reports as the HTTP client pulls each chunk, in steps of at least 5%.
+    let body = futures_util::stream::iter(chunks).map(move |chunk| {
+        sent += chunk.len() as u64;
+        let percent = (sent * 100).checked_div(total).unwrap_or(100);
+        if last_percent.is_none_or(|last| percent >= last + 5 || percent == 100) {
+            last_percent = Some(percent);
+            upload_progress.bytes("ipfs_add", format!("Uploading '{}' to IPFS ({}%)", upload_name, percent), sent, total);
+        }
+        Ok::<_, std::io::Error>(chunk)
+    });
+
+    let part = reqwest::multipart::Part::stream_with_length(reqwest::Body::wrap_stream(body), total)
+        .file_name(file_name)
+        .mime_str("application/octet-stream")
+        .map_err(|e| OmniError::Internal(format!("Failed to create multipart part: {}", e)))?;
+    let form = reqwest::multipart::Form::new().part("file", part);
+
+    let client = reqwest::Client::new();
+    let response = send_to_ipfs(client.post(format!("{}/add", ipfs_api_url())).multipart(form), "/add").await?;
+    progress.stage("ipfs_add", "Upload complete, waiting for IPFS to return the CID...");
+    let add_data = response
+        .json::<IpfsAddResponse>()
+        .await
+        .map_err(|e| OmniError::Ipfs(format!("Successfully uploaded but failed to parse IPFS add response: {}", e)))?;
+    events::publish(CoreEvent::IpfsAddCompleted { file_name: add_data.name.clone(), cid: add_data.hash.clone(), size: add_data.size.clone() });
+    Ok(CommandResponse::success(
+        format!("File '{}' successfully added to IPFS.", add_data.name),
+        Some(serde_json::json!({ "fileName": add_data.name, "cid": add_data.hash, "size": add_data.size })),
+    ))
+}
+
+pub async fn cat_file_from_ipfs_for_api(cid_str: &str, progress: &Progress) -> CommandResult {
+    if (!cid_str.starts_with("Qm") && !cid_str.starts_with("ba")) || cid_str.len() < 46 {
+        return Err(OmniError::InvalidCid(cid_str.to_string()));
+    }
+    progress.stage("ipfs_cat", format!("Fetching {} from IPFS...", cid_str));
+    let client = reqwest::Client::new();
+    let request = client.post(format!("{}/cat", ipfs_api_url())).query(&[("arg", cid_str)]);
+    let response = send_to_ipfs(request, "/cat").await?;
+    // Same cap as uploads: refuse early on a declared length, then count as the body streams in.
+    if let Some(length) = response.content_length() {
+        limits::check_upload_size(length)?;
+    }
+    let mut bytes = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| OmniError::Ipfs(format!("Failed to read content for CID {}: {}", cid_str, e)))?;
+        limits::check_upload_size((bytes.len() + chunk.len()) as u64)?;
+        bytes.extend_from_slice(&chunk);
+    }
+    let content = String::from_utf8_lossy(&bytes).into_owned();
+    Ok(CommandResponse::success(
+        format!("Successfully retrieved content for CID: {}", cid_str),
+        Some(serde_json::json!({ "cid": cid_str, "content": content })),
+    ))
+}
@@ -0,0 +1,696 @@
+// src/config.rs for omnimind-core
+// The effective configuration: built-in defaults, then omnimind.toml, then
+// OMNIMIND_* environment variables, then command-line flags. Each layer only
+// overrides the keys it sets, and every key remembers which layer it came from
+// so errors and `omnimind-core config show` can say so.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::OmniError;
+use crate::nlu::NluBackendKind;
+
+pub const DEFAULT_CONFIG_FILE: &str = "./omnimind.toml";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub nlu: NluConfig,
+    pub storage: StorageConfig,
+    pub ipfs: IpfsConfig,
+    pub auth: AuthConfig,
+    pub history: HistoryConfig,
+    pub log: LogConfig,
+    pub limits: LimitsConfig,
+    pub aliases: BTreeMap<String, String>, // Shortcut name -> command line, e.g. ll = "ls omni_notes"
+}
+
+// ServerConfig: Listeners and CORS (see server.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind: String, // host:port, or "none" to listen only on the Unix socket
+    pub unix_socket: Option<PathBuf>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub cors_origins: Vec<String>, // Exact origins, patterns like https://*.vercel.app, or "*"
+}
+
+// NluConfig: Backend selection and call/cache policy (see nlu/mod.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NluConfig {
+    pub backend: String, // python, http, rules or none
+    pub python: String,
+    pub script: PathBuf,
+    pub url: String,
+    pub model_version: Option<String>,
+    pub confidence_threshold: f64, // Minimum confidence needed to act on a classified intent
+    pub timeout_ms: u64,
+    pub retries: u32,
+    pub cache_size: usize, // 0 disables the cache
+    pub cache_ttl_secs: Option<u64>,
+    pub cache_file: Option<PathBuf>,
+}
+
+// StorageConfig: Where local state lives
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub notes_dir: PathBuf,
+    pub corrections_file: PathBuf,
+    pub tokens_file: PathBuf,
+    pub repl_history_file: PathBuf, // Line history of `omnimind-core repl`
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpfsConfig {
+    pub api_url: String, // Base of the Kubo RPC API, e.g. http://127.0.0.1:5001/api/v0
+}
+
+// AuthConfig: Sign-In with Ethereum (see siwe.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub siwe_domains: Vec<String>,
+    pub wallet_scopes: Vec<String>, // Scopes for wallets not listed in `wallets`; empty rejects them
+    pub wallets: BTreeMap<String, Vec<String>>, // Wallet address -> its scopes, e.g. "0xAb5801a7..." = ["notes:*"]
+}
+
+// HistoryConfig: The command history and audit log (see history.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub file: PathBuf, // JSON Lines, one entry per executed command
+    pub max_file_bytes: u64, // Rotate once the file reaches this size; 0 never rotates
+    pub max_files: usize, // Rotated files kept (file.1 is the newest); older ones are deleted
+    pub anchor_interval_secs: u64, // How often the server adds the chain head to IPFS; 0 never does
+    pub anchors_file: PathBuf, // CIDs of the anchors added so far
+}
+
+// LogConfig: Diagnostics on standard error (see logging.rs)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub level: String, // EnvFilter directives, e.g. "info" or "info,omnimind_core=debug,nlu=warn"
+    pub format: String, // "text" or "json" (one object per line)
+}
+
+// LimitsConfig: Per-caller rate limits and size/concurrency caps on the API (see limits.rs); 0 turns a limit off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub rate_limit_per_minute: u32, // Sustained requests per API token, or per IP address without one
+    pub rate_limit_burst: u32, // Requests a caller may make at once before the rate applies
+    pub max_command_length: usize, // Bytes of raw_command accepted from POST /command and /ws
+    pub max_upload_bytes: u64, // Largest file ipfs_add or POST /ipfs/add will read
+    pub nlu_max_concurrent: usize, // NLU requests in flight at once; more fail with nlu_busy
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: "127.0.0.1:3030".to_string(),
+            unix_socket: None,
+            tls_cert: None,
+            tls_key: None,
+            cors_origins: vec!["http://localhost:3000".to_string()],
+        }
+    }
+}
+
+impl Default for NluConfig {
+    fn default() -> Self {
+        NluConfig {
+            backend: "python".to_string(),
+            python: "python".to_string(),
+            // Relative to the working directory: assumes `cargo run` from omnimind-core/.
+            script: PathBuf::from("../omnimind-nlu-py/nlu_server.py"),
+            url: "http://127.0.0.1:5005/classify".to_string(),
+            model_version: None,
+            confidence_threshold: 0.5,
+            timeout_ms: 30_000, // Zero-shot classification on CPU can take a few seconds
+            retries: 1,
+            cache_size: 512,
+            cache_ttl_secs: None,
+            cache_file: None,
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            notes_dir: PathBuf::from("./omni_notes"),
+            corrections_file: PathBuf::from("./omni_data/nlu_corrections.json"),
+            tokens_file: PathBuf::from("./omni_data/tokens.json"),
+            repl_history_file: PathBuf::from("./omni_data/repl_history.txt"),
+        }
+    }
+}
+
+impl Default for IpfsConfig {
+    fn default() -> Self {
+        IpfsConfig { api_url: "http://127.0.0.1:5001/api/v0".to_string() }
+    }
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: true,
+            file: PathBuf::from("./omni_data/history.jsonl"),
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+            anchor_interval_secs: 0,
+            anchors_file: PathBuf::from("./omni_data/history_anchors.jsonl"),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig { level: "info".to_string(), format: "text".to_string() }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            rate_limit_per_minute: 300,
+            rate_limit_burst: 60,
+            max_command_length: 4096,
+            max_upload_bytes: 100 * 1024 * 1024,
+            nlu_max_concurrent: 4,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            siwe_domains: vec!["localhost:3000".to_string(), "127.0.0.1:3000".to_string()],
+            wallet_scopes: Vec::new(),
+            wallets: BTreeMap::new(),
+        }
+    }
+}
+
+// Environment variables and the keys they set.
+pub const ENV_VARS: &[(&str, &str)] = &[
+    ("OMNIMIND_BIND", "server.bind"),
+    ("OMNIMIND_UNIX_SOCKET", "server.unix_socket"),
+    ("OMNIMIND_TLS_CERT", "server.tls_cert"),
+    ("OMNIMIND_TLS_KEY", "server.tls_key"),
+    ("OMNIMIND_CORS_ORIGINS", "server.cors_origins"),
+    ("OMNIMIND_NLU_BACKEND", "nlu.backend"),
+    ("OMNIMIND_PYTHON", "nlu.python"),
+    ("OMNIMIND_NLU_SCRIPT", "nlu.script"),
+    ("OMNIMIND_NLU_URL", "nlu.url"),
+    ("OMNIMIND_NLU_MODEL_VERSION", "nlu.model_version"),
+    ("OMNIMIND_NLU_CONFIDENCE_THRESHOLD", "nlu.confidence_threshold"),
+    ("OMNIMIND_NLU_TIMEOUT_MS", "nlu.timeout_ms"),
+    ("OMNIMIND_NLU_RETRIES", "nlu.retries"),
+    ("OMNIMIND_NLU_CACHE_SIZE", "nlu.cache_size"),
+    ("OMNIMIND_NLU_CACHE_TTL_SECS", "nlu.cache_ttl_secs"),
+    ("OMNIMIND_NLU_CACHE_FILE", "nlu.cache_file"),
+    ("OMNIMIND_NOTES_DIR", "storage.notes_dir"),
+    ("OMNIMIND_CORRECTIONS_FILE", "storage.corrections_file"),
+    ("OMNIMIND_TOKENS_FILE", "storage.tokens_file"),
+    ("OMNIMIND_REPL_HISTORY_FILE", "storage.repl_history_file"),
+    ("OMNIMIND_IPFS_API_URL", "ipfs.api_url"),
+    ("OMNIMIND_SIWE_DOMAINS", "auth.siwe_domains"),
+    ("OMNIMIND_WALLET_SCOPES", "auth.wallet_scopes"),
+    ("OMNIMIND_HISTORY", "history.enabled"),
+    ("OMNIMIND_HISTORY_FILE", "history.file"),
+    ("OMNIMIND_HISTORY_MAX_FILE_BYTES", "history.max_file_bytes"),
+    ("OMNIMIND_HISTORY_MAX_FILES", "history.max_files"),
+    ("OMNIMIND_HISTORY_ANCHOR_INTERVAL_SECS", "history.anchor_interval_secs"),
+    ("OMNIMIND_HISTORY_ANCHORS_FILE", "history.anchors_file"),
+    ("OMNIMIND_LOG", "log.level"),
+    ("OMNIMIND_LOG_FORMAT", "log.format"),
+    ("OMNIMIND_RATE_LIMIT_PER_MINUTE", "limits.rate_limit_per_minute"),
+    ("OMNIMIND_RATE_LIMIT_BURST", "limits.rate_limit_burst"),
+    ("OMNIMIND_MAX_COMMAND_LENGTH", "limits.max_command_length"),
+    ("OMNIMIND_MAX_UPLOAD_BYTES", "limits.max_upload_bytes"),
+    ("OMNIMIND_NLU_MAX_CONCURRENT", "limits.nlu_max_concurrent"),
+];
+
+impl Config {
+    // Sets one dotted key from its text form (environment variables and --set).
+    // Lists are comma-separated; an empty value clears optional keys.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let text = || value.to_string();
+        let path = || PathBuf::from(value);
+        let optional_path = || (!value.is_empty()).then(|| PathBuf::from(value));
+        let list = || value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect::<Vec<_>>();
+        match key {
+            "server.bind" => self.server.bind = text(),
+            "server.unix_socket" => self.server.unix_socket = optional_path(),
+            "server.tls_cert" => self.server.tls_cert = optional_path(),
+            "server.tls_key" => self.server.tls_key = optional_path(),
+            "server.cors_origins" => self.server.cors_origins = list(),
+            "nlu.backend" => self.nlu.backend = text(),
+            "nlu.python" => self.nlu.python = text(),
+            "nlu.script" => self.nlu.script = path(),
+            "nlu.url" => self.nlu.url = text(),
+            "nlu.model_version" => self.nlu.model_version = (!value.is_empty()).then(text),
+            "nlu.confidence_threshold" => self.nlu.confidence_threshold = value.parse().map_err(|_| format!("expected a number, got '{}'", value))?,
+            "nlu.timeout_ms" => self.nlu.timeout_ms = parse_number(value)?,
+            "nlu.retries" => self.nlu.retries = parse_number(value)?,
+            "nlu.cache_size" => self.nlu.cache_size = parse_number(value)?,
+            "nlu.cache_ttl_secs" => self.nlu.cache_ttl_secs = if value.is_empty() { None } else { Some(parse_number(value)?) },
+            "nlu.cache_file" => self.nlu.cache_file = optional_path(),
+            "storage.notes_dir" => self.storage.notes_dir = path(),
+            "storage.corrections_file" => self.storage.corrections_file = path(),
+            "storage.tokens_file" => self.storage.tokens_file = path(),
+            "storage.repl_history_file" => self.storage.repl_history_file = path(),
+            "ipfs.api_url" => self.ipfs.api_url = text().trim_end_matches('/').to_string(),
+            "auth.siwe_domains" => self.auth.siwe_domains = list(),
+            "auth.wallet_scopes" => self.auth.wallet_scopes = list(),
+            "history.enabled" => self.history.enabled = parse_bool(value)?,
+            "history.file" => self.history.file = path(),
+            "history.max_file_bytes" => self.history.max_file_bytes = parse_number(value)?,
+            "history.max_files" => self.history.max_files = parse_number(value)?,
+            "history.anchor_interval_secs" => self.history.anchor_interval_secs = parse_number(value)?,
+            "history.anchors_file" => self.history.anchors_file = path(),
+            "log.level" => self.log.level = text(),
+            "log.format" => self.log.format = text().to_lowercase(),
+            "limits.rate_limit_per_minute" => self.limits.rate_limit_per_minute = parse_number(value)?,
+            "limits.rate_limit_burst" => self.limits.rate_limit_burst = parse_number(value)?,
+            "limits.max_command_length" => self.limits.max_command_length = parse_number(value)?,
+            "limits.max_upload_bytes" => self.limits.max_upload_bytes = parse_number(value)?,
+            "limits.nlu_max_concurrent" => self.limits.nlu_max_concurrent = parse_number(value)?,
+            _ if key.starts_with("auth.wallets.") => {
+                let address = &key["auth.wallets.".len()..];
+                if value.is_empty() {
+                    self.auth.wallets.remove(address);
+                } else {
+                    self.auth.wallets.insert(address.to_string(), list());
+                }
+            }
+            _ if key.starts_with("aliases.") => {
+                let name = &key["aliases.".len()..];
+                if value.is_empty() {
+                    self.aliases.remove(name);
+                } else {
+                    self.aliases.insert(name.to_string(), text());
+                }
+            }
+            _ => return Err(format!("unknown key (known keys: {}, auth.wallets.<address>, aliases.<name>)", ENV_VARS.iter().map(|(_, key)| *key).collect::<Vec<_>>().join(", "))),
+        }
+        Ok(())
+    }
+
+    // Checks values that parse but make no sense; errors name the key and where it was set.
+    fn validate(&self, sources: &Sources) -> Result<(), OmniError> {
+        let invalid = |key: &str, problem: String| Err(OmniError::Config(format!("{} ({}): {}", key, sources.of(key), problem)));
+
+        let bind = &self.server.bind;
+        if !bind.eq_ignore_ascii_case("none") && bind.rsplit_once(':').and_then(|(_, port)| port.parse::<u16>().ok()).is_none() {
+            return invalid("server.bind", format!("'{}' is not host:port (e.g. 0.0.0.0:3030) or none", bind));
+        }
+        if bind.eq_ignore_ascii_case("none") && self.server.unix_socket.is_none() {
+            return invalid("server.bind", "none needs server.unix_socket, or the server has nowhere to listen".to_string());
+        }
+        if self.server.unix_socket.is_some() && !cfg!(unix) {
+            return invalid("server.unix_socket", "Unix domain sockets are only supported on Unix".to_string());
+        }
+        if self.server.tls_cert.is_some() != self.server.tls_key.is_some() {
+            return invalid("server.tls_cert", "server.tls_cert and server.tls_key must be set together".to_string());
+        }
+        if let Some(bad) = self.server.cors_origins.iter().find(|origin| *origin != "*" && !is_http_url(origin)) {
+            return invalid("server.cors_origins", format!("'{}' must start with http:// or https:// (or be *)", bad));
+        }
+
+        if NluBackendKind::parse(&self.nlu.backend).is_none() {
+            return invalid("nlu.backend", format!("'{}', expected python, http, rules or none", self.nlu.backend));
+        }
+        if !(0.0..=1.0).contains(&self.nlu.confidence_threshold) {
+            return invalid("nlu.confidence_threshold", format!("{} is not between 0 and 1", self.nlu.confidence_threshold));
+        }
+        if self.nlu.timeout_ms == 0 {
+            return invalid("nlu.timeout_ms", "must be greater than 0".to_string());
+        }
+        if !is_http_url(&self.nlu.url) {
+            return invalid("nlu.url", format!("'{}' must start with http:// or https://", self.nlu.url));
+        }
+        if !is_http_url(&self.ipfs.api_url) {
+            return invalid("ipfs.api_url", format!("'{}' must start with http:// or https://", self.ipfs.api_url));
+        }
+        if self.storage.notes_dir.as_os_str().is_empty() {
+            return invalid("storage.notes_dir", "cannot be empty".to_string());
+        }
+        if self.history.file.as_os_str().is_empty() {
+            return invalid("history.file", "cannot be empty".to_string());
+        }
+        if let Err(e) = crate::logging::check_level(&self.log.level) {
+            return invalid("log.level", format!("'{}' is not a valid filter: {}", self.log.level, e));
+        }
+        if self.log.format != "text" && self.log.format != "json" {
+            return invalid("log.format", format!("'{}', expected text or json", self.log.format));
+        }
+        if self.limits.rate_limit_per_minute > 0 && self.limits.rate_limit_burst == 0 {
+            return invalid("limits.rate_limit_burst", "must be at least 1 while limits.rate_limit_per_minute is set".to_string());
+        }
+        if let Some(bad) = self.auth.wallet_scopes.iter().find(|scope| !crate::auth::is_known_scope(scope)) {
+            return invalid("auth.wallet_scopes", format!("unknown scope '{}' (see `omnimind-core token scopes`)", bad));
+        }
+        for (address, scopes) in &self.auth.wallets {
+            let key = format!("auth.wallets.{}", address);
+            if !crate::siwe::is_hex_address(address) {
+                return invalid(&key, format!("'{}' is not a 0x-prefixed 40-digit address", address));
+            }
+            if let Some(bad) = scopes.iter().find(|scope| !crate::auth::is_known_scope(scope)) {
+                return invalid(&key, format!("unknown scope '{}' (see `omnimind-core token scopes`)", bad));
+            }
+        }
+        for (name, command) in &self.aliases {
+            let key = format!("aliases.{}", name);
+            if name.is_empty() || name.chars().any(char::is_whitespace) {
+                return invalid(&key, "an alias name must be one word".to_string());
+            }
+            if crate::registry::find(&name.to_lowercase()).is_some() || RESERVED_WORDS.contains(&name.to_lowercase().as_str()) {
+                return invalid(&key, format!("'{}' is a built-in command or answer and cannot be redefined", name));
+            }
+            let target = command.split_whitespace().next().unwrap_or("").to_lowercase();
+            if crate::registry::find(&target).is_none() {
+                return invalid(&key, format!("'{}' does not start with a built-in command (aliases cannot refer to other aliases)", command));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Answers to "did you mean" questions, which an alias must not shadow.
+const RESERVED_WORDS: &[&str] = &["yes", "y", "no", "n"];
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("expected a non-negative integer, got '{}'", value))
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        _ => Err(format!("expected true or false, got '{}'", value)),
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+// --- Loading ---
+
+// Sources: Which layer set each key that is not at its default
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    pub file: Option<PathBuf>, // The config file that was read, if any
+    keys: BTreeMap<String, String>,
+}
+
+impl Sources {
+    pub fn of(&self, key: &str) -> &str {
+        self.keys.get(key).map(String::as_str).unwrap_or("default")
+    }
+}
+
+// Loads defaults < file < environment < `overrides` (key, value pairs from
+// command-line flags). `file` is the --config flag; without it OMNIMIND_CONFIG,
+// then ./omnimind.toml if present, is used.
+pub fn load(file: Option<&Path>, overrides: &[(String, String)]) -> Result<(Config, Sources), OmniError> {
+    let file = match explicit_file(file) {
+        Some(path) if !path.exists() => return Err(OmniError::Config(format!("config file '{}' does not exist.", path.display()))),
+        Some(path) => Some(path),
+        None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+    };
+    let text = match &file {
+        Some(path) => Some(std::fs::read_to_string(path).map_err(|e| OmniError::io(format!("Failed to read config file '{}'", path.display()), e))?),
+        None => None,
+    };
+    let environment: Vec<(String, String)> = ENV_VARS.iter().filter_map(|(variable, _)| Some((variable.to_string(), std::env::var(variable).ok()?))).collect();
+    layer(file.as_deref().zip(text.as_deref()), &environment, overrides)
+}
+
+// Applies the file (its path and text), OMNIMIND_* variables (name, value) and
+// `overrides` to the defaults, in that order, then validates the result.
+fn layer(file: Option<(&Path, &str)>, environment: &[(String, String)], overrides: &[(String, String)]) -> Result<(Config, Sources), OmniError> {
+    let mut sources = Sources::default();
+    let mut config = match file {
+        Some((path, text)) => {
+            let table: toml::Table = toml::from_str(text).map_err(|e| file_error(path, e))?;
+            let config = Config::deserialize(toml::Value::Table(table.clone())).map_err(|e| file_error(path, e))?;
+            for (section, values) in &table {
+                if let Some(values) = values.as_table() {
+                    for key in values.keys() {
+                        sources.keys.insert(format!("{}.{}", section, key), path.display().to_string());
+                    }
+                }
+            }
+            sources.file = Some(path.to_path_buf());
+            config
+        }
+        None => Config::default(),
+    };
+
+    for (variable, key) in ENV_VARS {
+        if let Some((_, value)) = environment.iter().find(|(name, _)| name == variable) {
+            config.set(key, value).map_err(|problem| OmniError::Config(format!("{}='{}': {}", variable, value, problem)))?;
+            sources.keys.insert(key.to_string(), variable.to_string());
+        }
+    }
+    for (key, value) in overrides {
+        config.set(key, value).map_err(|problem| OmniError::Config(format!("--set {}={}: {}", key, value, problem)))?;
+        sources.keys.insert(key.clone(), "command line".to_string());
+    }
+
+    config.validate(&sources)?;
+    Ok((config, sources))
+}
+
+// toml errors span several lines (with a source excerpt for syntax errors); keep
+// the excerpt but drop the trailing blank line.
+fn file_error(path: &Path, error: impl std::fmt::Display) -> OmniError {
+    OmniError::Config(format!("'{}': {}", path.display(), error.to_string().trim_end()))
+}
+
+fn explicit_file(file: Option<&Path>) -> Option<PathBuf> {
+    file.map(Path::to_path_buf).or_else(|| std::env::var("OMNIMIND_CONFIG").ok().filter(|path| !path.trim().is_empty()).map(PathBuf::from))
+}
+
+// The file `load` reads, or would read once it exists (see reload.rs).
+pub fn file_to_watch(file: Option<&Path>) -> PathBuf {
+    explicit_file(file).unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE))
+}
+
+// Dotted keys whose values differ between two configurations.
+pub fn changed_keys(old: &Config, new: &Config) -> Vec<String> {
+    let flatten = |config: &Config| -> BTreeMap<String, toml::Value> {
+        let mut keys = BTreeMap::new();
+        if let Ok(toml::Value::Table(sections)) = toml::Value::try_from(config) {
+            for (section, values) in sections {
+                for (key, value) in values.as_table().cloned().unwrap_or_default() {
+                    keys.insert(format!("{}.{}", section, key), value);
+                }
+            }
+        }
+        keys
+    };
+    let (old, new) = (flatten(old), flatten(new));
+    let mut changed: Vec<String> = old.keys().chain(new.keys()).filter(|key| old.get(*key) != new.get(*key)).cloned().collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+// The effective config as TOML, each non-default key annotated with its source.
+pub fn render(config: &Config, sources: &Sources) -> String {
+    let text = toml::to_string(config).unwrap_or_default();
+    let mut section = String::new();
+    let mut lines = vec![match &sources.file {
+        Some(path) => format!("# Effective configuration (defaults < {} < environment < command line)", path.display()),
+        None => "# Effective configuration (defaults < environment < command line; no config file found)".to_string(),
+    }];
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.to_string();
+        }
+        match line.split_once(" = ") {
+            Some((key, _)) if sources.of(&format!("{}.{}", section, key)) != "default" => {
+                lines.push(format!("{:<60} # {}", line, sources.of(&format!("{}.{}", section, key))));
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines.join("\n")
+}
+
+// --- Current configuration ---
+
+static CURRENT: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+fn slot() -> &'static RwLock<Arc<Config>> {
+    CURRENT.get_or_init(|| RwLock::new(Arc::new(Config::default())))
+}
+
+// The configuration in effect; defaults until `install` is called.
+pub fn current() -> Arc<Config> {
+    slot().read().map(|config| Arc::clone(&config)).unwrap_or_default()
+}
+
+pub fn install(config: Config) {
+    if let Ok(mut current) = slot().write() {
+        *current = Arc::new(config);
+    }
+}
+
+// --- Tests ---
+
+// Tests that install a config, or depend on state sized by it (rate limits, NLU
+// permits), hold a ConfigGuard so they run one at a time; the config that was in
+// effect comes back when the guard drops.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    static TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    // ConfigGuard: A test's claim on the current configuration
+    pub(crate) struct ConfigGuard {
+        previous: Arc<Config>,
+        _lock: tokio::sync::MutexGuard<'static, ()>,
+    }
+
+    pub(crate) async fn install(config: Config) -> ConfigGuard {
+        let lock = TEST_LOCK.lock().await;
+        let previous = current();
+        super::install(config);
+        ConfigGuard { previous, _lock: lock }
+    }
+
+    impl Drop for ConfigGuard {
+        fn drop(&mut self) {
+            super::install(Config::clone(&self.previous));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn layered(file: Option<&str>, environment: &[(&str, &str)], overrides: &[(&str, &str)]) -> Result<(Config, Sources), OmniError> {
+        layer(file.map(|text| (Path::new("test.toml"), text)), &pairs(environment), &pairs(overrides))
+    }
+
+    fn error_of(result: Result<(Config, Sources), OmniError>) -> String {
+        match result {
+            Err(error) => error.to_string(),
+            Ok(_) => panic!("expected a config error"),
+        }
+    }
+
+    #[test]
+    fn each_layer_overrides_the_one_before() {
+        let file = "[nlu]\ntimeout_ms = 1000\nretries = 3\n\n[log]\nlevel = \"debug\"\n";
+        let environment = [("OMNIMIND_NLU_TIMEOUT_MS", "2000"), ("OMNIMIND_NLU_RETRIES", "4")];
+        let (config, sources) = layered(Some(file), &environment, &[("nlu.retries", "5")]).unwrap();
+
+        assert_eq!(config.log.level, "debug");
+        assert_eq!(config.nlu.timeout_ms, 2000);
+        assert_eq!(config.nlu.retries, 5);
+        assert_eq!(config.server.bind, ServerConfig::default().bind);
+        assert_eq!(sources.of("log.level"), "test.toml");
+        assert_eq!(sources.of("nlu.timeout_ms"), "OMNIMIND_NLU_TIMEOUT_MS");
+        assert_eq!(sources.of("nlu.retries"), "command line");
+        assert_eq!(sources.of("server.bind"), "default");
+    }
+
+    #[test]
+    fn variables_that_are_not_ours_are_ignored() {
+        let (config, _) = layered(None, &[("OMNIMIND_UNKNOWN", "1"), ("PATH", "/bin")], &[]).unwrap();
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn set_parses_lists_and_clears_optional_keys() {
+        let mut config = Config::default();
+        config.set("server.cors_origins", "https://a.example, ,https://b.example").unwrap();
+        assert_eq!(config.server.cors_origins, ["https://a.example", "https://b.example"]);
+        config.set("nlu.cache_ttl_secs", "60").unwrap();
+        config.set("nlu.cache_ttl_secs", "").unwrap();
+        assert_eq!(config.nlu.cache_ttl_secs, None);
+        config.set("history.enabled", "off").unwrap();
+        assert!(!config.history.enabled);
+        config.set("aliases.ll", "ls omni_notes").unwrap();
+        config.set("aliases.ll", "").unwrap();
+        assert!(config.aliases.is_empty());
+    }
+
+    #[test]
+    fn set_rejects_unknown_keys_and_bad_values() {
+        let mut config = Config::default();
+        assert!(config.set("nlu.timeout", "10").unwrap_err().starts_with("unknown key"));
+        assert!(config.set("server", "x").unwrap_err().starts_with("unknown key"));
+        assert_eq!(config.set("nlu.retries", "-1").unwrap_err(), "expected a non-negative integer, got '-1'");
+        assert_eq!(config.set("history.enabled", "maybe").unwrap_err(), "expected true or false, got 'maybe'");
+        assert!(config.set("nlu.confidence_threshold", "high").is_err());
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn errors_name_the_layer_that_set_the_value() {
+        assert!(error_of(layered(Some("[nlu]\ntimeout = 5\n"), &[], &[])).contains("'test.toml'"));
+        assert!(error_of(layered(Some("[nlu\n"), &[], &[])).contains("'test.toml'"));
+        assert!(error_of(layered(None, &[("OMNIMIND_NLU_RETRIES", "many")], &[])).ends_with("OMNIMIND_NLU_RETRIES='many': expected a non-negative integer, got 'many'"));
+        assert!(error_of(layered(None, &[], &[("nlu.nope", "1")])).contains("--set nlu.nope=1: unknown key"));
+    }
+
+    #[test]
+    fn validation_rejects_values_that_parse_but_make_no_sense() {
+        let error = error_of(layered(None, &[("OMNIMIND_BIND", "localhost")], &[]));
+        assert!(error.contains("server.bind (OMNIMIND_BIND): 'localhost' is not host:port"), "{}", error);
+        let cases = [
+            ("nlu.confidence_threshold", "1.5", "nlu.confidence_threshold (command line): 1.5 is not between 0 and 1"),
+            ("nlu.backend", "gpt", "nlu.backend (command line): 'gpt', expected"),
+            ("nlu.timeout_ms", "0", "nlu.timeout_ms (command line): must be greater than 0"),
+            ("limits.rate_limit_burst", "0", "limits.rate_limit_burst (command line): must be at least 1"),
+            ("server.tls_cert", "cert.pem", "server.tls_cert and server.tls_key must be set together"),
+            ("aliases.ls", "help", "'ls' is a built-in command"),
+            ("auth.wallet_scopes", "notes:everything", "unknown scope 'notes:everything'"),
+        ];
+        for (key, value, expected) in cases {
+            let error = error_of(layered(None, &[], &[(key, value)]));
+            assert!(error.contains(expected), "{} does not mention {}", error, expected);
+        }
+    }
+
+    #[test]
+    fn validation_names_the_file_for_values_it_set() {
+        let error = error_of(layered(Some("[log]\nformat = \"xml\"\n"), &[], &[]));
+        assert!(error.contains("log.format (test.toml): 'xml', expected text or json"), "{}", error);
+    }
+
+    #[test]
+    fn changed_keys_lists_the_dotted_keys_that_differ() {
+        let old = Config::default();
+        let mut new = old.clone();
+        assert!(changed_keys(&old, &new).is_empty());
+        new.set("nlu.retries", "7").unwrap();
+        new.set("aliases.ll", "ls omni_notes").unwrap();
+        new.set("nlu.model_version", "v2").unwrap();
+        assert_eq!(changed_keys(&old, &new), ["aliases.ll", "nlu.model_version", "nlu.retries"]);
+        assert_eq!(changed_keys(&new, &old), ["aliases.ll", "nlu.model_version", "nlu.retries"]);
+    }
+}
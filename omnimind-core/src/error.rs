@@ -0,0 +1,198 @@
+// src/error.rs for omnimind-core
+// OmniError: every way a command can fail, and the one conversion into a CommandResponse.
+use std::error::Error as _;
+
+use thiserror::Error;
+
+use crate::nlu::NluError;
+use crate::planner::PlanError;
+use crate::response::{CommandResponse, CommandStatus, ErrorCode};
+
+// CommandResult: What command implementations return
+pub type CommandResult = Result<CommandResponse, OmniError>;
+
+#[derive(Debug, Error)]
+pub enum OmniError {
+    // --- Validation ---
+    #[error("Empty command.")]
+    EmptyCommand,
+    #[error("{0}")]
+    UnknownCommand(String),
+    #[error("{0}")]
+    InvalidArguments(String),
+    #[error("Note title invalid after sanitization.")]
+    InvalidNoteTitle,
+    #[error("A note with the id '{0}' already exists.")]
+    NoteExists(String),
+    #[error("Invalid CID format: '{0}'")]
+    InvalidCid(String),
+    #[error("{0}")]
+    NotFound(String),
+
+    // --- Filesystem ---
+    #[error("Path '{0}' does not exist.")]
+    PathNotFound(String),
+    #[error("Path '{0}' is not a directory.")]
+    NotADirectory(String),
+    #[error("Path '{0}' is not a file.")]
+    NotAFile(String),
+    #[error("{context}: {source}")]
+    Io { context: String, #[source] source: std::io::Error },
+
+    // --- IPFS ---
+    #[error("Failed to connect to IPFS API {endpoint}: {source}. Ensure IPFS daemon is running and API server is enabled.")]
+    IpfsUnreachable { endpoint: String, #[source] source: reqwest::Error },
+    #[error("{0}")]
+    Ipfs(String),
+
+    // --- NLU and plans ---
+    #[error(transparent)]
+    Nlu(#[from] NluError),
+    #[error("{message}")]
+    LowConfidence { message: String, details: serde_json::Value },
+    #[error(transparent)]
+    Plan(#[from] PlanError),
+    #[error("Plan stopped at step {step} of {total}.")]
+    PlanStepFailed { step: usize, total: usize, code: Option<ErrorCode>, details: serde_json::Value },
+
+    // --- Authentication ---
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("Token '{token}' does not have the '{scope}' scope.")]
+    Forbidden { token: String, scope: String },
+
+    // --- Audit ---
+    #[error("{message}")]
+    AuditTampered { message: String, details: serde_json::Value },
+
+    // --- Limits ---
+    #[error("Too many requests; try again in {retry_after_secs} s.")]
+    RateLimited { retry_after_secs: u64 },
+    #[error("{0}")]
+    CommandTooLong(String),
+    #[error("{0}")]
+    UploadTooLarge(String),
+
+    // --- Health ---
+    #[error("{message}")]
+    NotReady { message: String, details: serde_json::Value },
+
+    // --- Client ---
+    #[error("Cannot reach the OmniMind server at {url}: {reason}")]
+    ServerUnreachable { url: String, reason: String },
+
+    // --- Configuration and internals ---
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl OmniError {
+    // Wraps an io::Error with what we were doing when it happened.
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        OmniError::Io { context: context.into(), source }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            OmniError::EmptyCommand => ErrorCode::EmptyCommand,
+            OmniError::UnknownCommand(_) => ErrorCode::UnknownCommand,
+            OmniError::InvalidArguments(_) => ErrorCode::InvalidArguments,
+            OmniError::InvalidNoteTitle => ErrorCode::InvalidNoteTitle,
+            OmniError::NoteExists(_) => ErrorCode::NoteExists,
+            OmniError::InvalidCid(_) => ErrorCode::InvalidCid,
+            OmniError::NotFound(_) => ErrorCode::NotFound,
+            OmniError::PathNotFound(_) => ErrorCode::PathNotFound,
+            OmniError::NotADirectory(_) => ErrorCode::NotADirectory,
+            OmniError::NotAFile(_) => ErrorCode::NotAFile,
+            OmniError::Io { .. } => ErrorCode::IoError,
+            OmniError::IpfsUnreachable { .. } => ErrorCode::IpfsUnreachable,
+            OmniError::Ipfs(_) => ErrorCode::IpfsError,
+            OmniError::Nlu(error) => ErrorCode::from(error),
+            OmniError::LowConfidence { .. } => ErrorCode::NluLowConfidence,
+            OmniError::Plan(error) => ErrorCode::from(error),
+            OmniError::PlanStepFailed { code, .. } => code.unwrap_or(ErrorCode::InternalError),
+            OmniError::Unauthorized(_) => ErrorCode::Unauthorized,
+            OmniError::Forbidden { .. } => ErrorCode::Forbidden,
+            OmniError::AuditTampered { .. } => ErrorCode::AuditTampered,
+            OmniError::RateLimited { .. } => ErrorCode::RateLimited,
+            OmniError::CommandTooLong(_) => ErrorCode::CommandTooLong,
+            OmniError::UploadTooLarge(_) => ErrorCode::UploadTooLarge,
+            OmniError::NotReady { .. } => ErrorCode::NotReady,
+            OmniError::ServerUnreachable { .. } => ErrorCode::ServerUnreachable,
+            OmniError::Config(_) => ErrorCode::ConfigError,
+            OmniError::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    // Structured context that should travel with the error to the client.
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            OmniError::LowConfidence { details, .. } | OmniError::PlanStepFailed { details, .. } | OmniError::AuditTampered { details, .. } | OmniError::NotReady { details, .. } => Some(details.clone()),
+            OmniError::RateLimited { retry_after_secs } => Some(serde_json::json!({ "retry_after_secs": retry_after_secs })),
+            _ => None,
+        }
+    }
+}
+
+impl From<OmniError> for CommandResponse {
+    fn from(error: OmniError) -> Self {
+        let code = error.code();
+        // Failures on our side (not the caller's input) are worth a log line with the cause
+        // chain. A failed plan step was already logged when that step failed, an
+        // unreachable server is reported by the client that failed to reach it,
+        // readiness probes poll a failing dependency far too often to log each time,
+        // and NLU requests turned away under load are counted in metrics instead.
+        if code.http_status().is_server_error() && !matches!(error, OmniError::PlanStepFailed { .. } | OmniError::ServerUnreachable { .. } | OmniError::NotReady { .. } | OmniError::Nlu(NluError::Busy(_))) {
+            let line = format!("Command failed ({:?}): {}", code, with_causes(&error));
+            tracing::error!("{}", line);
+        }
+        CommandResponse { status: CommandStatus::Error, code: Some(code), message: error.to_string(), data: error.details() }
+    }
+}
+
+// Appends the error's cause chain to its message, skipping causes the message (or an
+// earlier cause) already includes, such as the io::Error inside OmniError::Io.
+fn with_causes(error: &OmniError) -> String {
+    let mut line = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let text = cause.to_string();
+        if !line.contains(&text) {
+            line.push_str(&format!(" <- {}", text));
+        }
+        source = cause.source();
+    }
+    line
+}
+
+// Collapses a CommandResult into the response we send back.
+pub fn into_response(result: CommandResult) -> CommandResponse {
+    result.unwrap_or_else(CommandResponse::from)
+}
+
+// --- Tests ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_io_error_names_its_cause_once() {
+        let error = OmniError::io("Failed to read 'a.txt'", std::io::Error::new(std::io::ErrorKind::PermissionDenied, "access denied"));
+        assert_eq!(with_causes(&error), "Failed to read 'a.txt': access denied");
+    }
+
+    // A wrapper whose message leaves out the error it wraps, as reqwest's do.
+    #[derive(Debug, Error)]
+    #[error("request failed")]
+    struct Wrapper(#[source] std::io::Error);
+
+    #[test]
+    fn causes_missing_from_the_message_are_appended() {
+        let wrapper = Wrapper(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset"));
+        let error = OmniError::io("Failed to fetch 'b.txt'", std::io::Error::other(wrapper));
+        assert_eq!(with_causes(&error), "Failed to fetch 'b.txt': request failed <- connection reset");
+    }
+}
@@ -0,0 +1,194 @@
+// src/events.rs for omnimind-core
+// Core activity events (commands executed, notes created/changed, IPFS adds,
+// NLU restarts, config reloads, audit anchors) on one process-wide broadcast bus, streamed to dashboards and
+// the React client as server-sent events at GET /events.
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::commands::{self, NOTE_EXTENSION};
+use crate::response::{CommandStatus, ErrorCode};
+
+// How many events a slow subscriber may fall behind before it starts missing some.
+const BUS_CAPACITY: usize = 256;
+// Comment lines keep idle connections from being closed by proxies.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+// How often the notes directory is rescanned for notes created or edited outside the API.
+const NOTES_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// CoreEvent: Something that happened in the core
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CoreEvent {
+    CommandExecuted {
+        session: String, // "http:<caller>" for POST /command, the session id for /ws, "rest" for the REST endpoints
+        raw_command: String,
+        status: CommandStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<ErrorCode>,
+        message: String,
+    },
+    NoteCreated { note_id: String, path: String },
+    NoteChanged { note_id: String, path: String },
+    NoteDeleted { note_id: String, path: String },
+    IpfsAddCompleted { file_name: String, cid: String, size: String },
+    NluBackendRestarted { backend: String, reason: String },
+    ConfigReloaded { changed: Vec<String> }, // Dotted config keys, e.g. "nlu.confidence_threshold"
+    AuditAnchored { cid: String, head_hash: String },
+}
+
+impl CoreEvent {
+    fn kind(&self) -> &'static str {
+        match self {
+            CoreEvent::CommandExecuted { .. } => "command_executed",
+            CoreEvent::NoteCreated { .. } => "note_created",
+            CoreEvent::NoteChanged { .. } => "note_changed",
+            CoreEvent::NoteDeleted { .. } => "note_deleted",
+            CoreEvent::IpfsAddCompleted { .. } => "ipfs_add_completed",
+            CoreEvent::NluBackendRestarted { .. } => "nlu_backend_restarted",
+            CoreEvent::ConfigReloaded { .. } => "config_reloaded",
+            CoreEvent::AuditAnchored { .. } => "audit_anchored",
+        }
+    }
+}
+
+// EventEnvelope: A CoreEvent with its sequence number and time
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    pub id: u64,
+    pub time: String,
+    #[serde(flatten)]
+    pub event: CoreEvent,
+}
+
+static BUS: OnceLock<broadcast::Sender<EventEnvelope>> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn bus() -> &'static broadcast::Sender<EventEnvelope> {
+    BUS.get_or_init(|| broadcast::channel(BUS_CAPACITY).0)
+}
+
+// Sends `event` to every current subscriber; a no-op when nobody listens.
+pub fn publish(event: CoreEvent) {
+    let envelope = EventEnvelope {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        time: chrono::Local::now().to_rfc3339(),
+        event,
+    };
+    let _ = bus().send(envelope);
+}
+
+pub fn subscribe() -> broadcast::Receiver<EventEnvelope> {
+    bus().subscribe()
+}
+
+// --- GET /events ---
+
+// EventsQuery: Optional comma-separated filter, e.g. ?types=note_created,ipfs_add_completed
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Comma-separated event types to receive (default: all).
+    pub types: Option<String>,
+}
+
+#[utoipa::path(
+    get, path = "/events", tag = "events",
+    params(EventsQuery),
+    responses((
+        status = 200,
+        description = "Server-sent event stream. Each event's name is its type (command_executed, note_created, note_changed, note_deleted, ipfs_add_completed, nlu_backend_restarted, config_reloaded, audit_anchored) and its data is a JSON object with id, time, type and the event fields.",
+        content_type = "text/event-stream",
+        body = String,
+    ))
+)]
+pub async fn events_stream(query: web::Query<EventsQuery>) -> HttpResponse {
+    let wanted: Option<HashSet<String>> = query.types.as_ref().map(|types| {
+        types.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect()
+    });
+    let receiver = subscribe();
+
+    let stream = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let wanted = wanted.clone();
+        async move {
+            loop {
+                let received = tokio::time::timeout(KEEP_ALIVE_INTERVAL, receiver.recv()).await;
+                let frame = match received {
+                    Err(_) => ": keep-alive\n\n".to_string(),
+                    Ok(Ok(envelope)) => {
+                        if wanted.as_ref().is_some_and(|wanted| !wanted.contains(envelope.event.kind())) {
+                            continue;
+                        }
+                        let data = serde_json::to_string(&envelope).unwrap_or_default();
+                        format!("id: {}\nevent: {}\ndata: {}\n\n", envelope.id, envelope.event.kind(), data)
+                    }
+                    // Tell the client it missed events rather than silently skipping them.
+                    Ok(Err(broadcast::error::RecvError::Lagged(missed))) => format!("event: lagged\ndata: {{\"missed\":{}}}\n\n", missed),
+                    Ok(Err(broadcast::error::RecvError::Closed)) => return None,
+                };
+                return Some((Ok::<_, actix_web::Error>(web::Bytes::from(frame)), receiver));
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream)
+}
+
+// --- Notes watcher ---
+
+// Polls the notes directory and publishes note_created / note_changed / note_deleted for
+// every difference, whether the change came from a command or an editor.
+pub async fn watch_notes() {
+    let mut known = scan_notes_in_background().await.unwrap_or_default();
+    let mut interval = tokio::time::interval(NOTES_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(current) = scan_notes_in_background().await else { continue };
+        for (id, (path, modified)) in &current {
+            match known.get(id) {
+                None => publish(CoreEvent::NoteCreated { note_id: id.clone(), path: path.clone() }),
+                Some((_, previous)) if previous != modified => publish(CoreEvent::NoteChanged { note_id: id.clone(), path: path.clone() }),
+                Some(_) => {}
+            }
+        }
+        for (id, (path, _)) in &known {
+            if !current.contains_key(id) {
+                publish(CoreEvent::NoteDeleted { note_id: id.clone(), path: path.clone() });
+            }
+        }
+        known = current;
+    }
+}
+
+// note id -> (path, (modified time, size))
+type NoteSnapshot = HashMap<String, (String, (Option<SystemTime>, u64))>;
+
+// The directory listing and metadata reads run on the blocking pool, off the
+// event loop. None if the scan task failed.
+async fn scan_notes_in_background() -> Option<NoteSnapshot> {
+    let notes_dir = commands::notes_dir();
+    tokio::task::spawn_blocking(move || scan_notes(&notes_dir)).await.ok()
+}
+
+fn scan_notes(notes_dir: &Path) -> NoteSnapshot {
+    let mut notes = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(notes_dir) else { return notes };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(id) = file_name.strip_suffix(NOTE_EXTENSION) else { continue };
+        let metadata = entry.metadata().ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        let size = metadata.map(|m| m.len()).unwrap_or(0);
+        notes.insert(id.to_string(), (entry.path().display().to_string(), (modified, size)));
+    }
+    notes
+}
@@ -0,0 +1,176 @@
+// src/health.rs for omnimind-core
+// Probes for orchestrators; neither needs a token. GET /healthz answers as long
+// as the process serves requests. GET /readyz checks what commands depend on:
+// the NLU backend (pinged; reports its model and last successful ping), the IPFS
+// API (/api/v0/id), that the notes directory is writable, and that the config
+// file still validates. It answers 200 when no check failed, otherwise 503 with
+// code not_ready; both carry every check's status in data.checks. The checks run
+// at most once per READY_CACHE_TTL, and only callers with metrics:read see their
+// details (paths, URLs, errors).
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use crate::api;
+use crate::auth::Principal;
+use crate::commands;
+use crate::error::OmniError;
+use crate::reload;
+use crate::response::CommandResponse;
+use crate::AppState;
+
+// Each check gives up after this long, so a hung dependency cannot hang the probe.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Probes within this long of the last round of checks get its result, so a flood
+// of them cannot ping the NLU backend or write to the notes directory each time.
+const READY_CACHE_TTL: Duration = Duration::from_secs(5);
+
+static STARTED: OnceLock<(Instant, DateTime<Local>)> = OnceLock::new();
+
+// LastPing: The NLU backend's last successful ping, kept for failed checks to report
+struct LastPing {
+    version: String, // Backend version; a replaced backend starts without a ping
+    model: Option<String>,
+    at: DateTime<Local>,
+}
+
+static LAST_NLU_PING: Mutex<Option<LastPing>> = Mutex::new(None);
+
+// Readiness: One round of checks
+struct Readiness {
+    at: Instant,
+    checked_at: DateTime<Local>,
+    checks: Vec<(&'static str, serde_json::Value)>,
+}
+
+// Held while a round runs, so concurrent probes wait for it instead of starting their own.
+static READINESS: tokio::sync::Mutex<Option<Readiness>> = tokio::sync::Mutex::const_new(None);
+
+// Called once the server starts, for started_at and uptime_secs.
+pub fn mark_started() {
+    let _ = STARTED.set((Instant::now(), Local::now()));
+}
+
+#[utoipa::path(
+    get, path = "/healthz", tag = "monitoring",
+    responses((status = 200, description = "The process is up; data has version, started_at and uptime_secs", body = CommandResponse))
+)]
+pub async fn healthz() -> HttpResponse {
+    let (started, started_at) = STARTED.get_or_init(|| (Instant::now(), Local::now()));
+    api::respond(Ok(CommandResponse::success("OmniMind Core is running.", Some(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "started_at": started_at.to_rfc3339(),
+        "uptime_secs": started.elapsed().as_secs(),
+    })))))
+}
+
+#[utoipa::path(
+    get, path = "/readyz", tag = "monitoring",
+    responses(
+        (status = 200, description = "Every dependency is usable; data.checks has nlu, ipfs, notes_dir and config, with details for metrics:read callers", body = CommandResponse),
+        (status = 503, description = "A check failed (code not_ready); data.checks says which, and why for metrics:read callers", body = CommandResponse),
+    )
+)]
+pub async fn readyz(app_state: web::Data<AppState>, req: HttpRequest) -> HttpResponse {
+    let mut cached = READINESS.lock().await;
+    let readiness = match cached.take() {
+        Some(readiness) if readiness.at.elapsed() < READY_CACHE_TTL => readiness,
+        _ => run_checks(&app_state).await,
+    };
+    let readiness = cached.insert(readiness);
+
+    let detailed = req.extensions().get::<Principal>().is_some_and(|principal| principal.allows("metrics:read"));
+    let failed: Vec<&str> = readiness.checks.iter().filter(|(_, check)| check["status"] == "error").map(|(name, _)| *name).collect();
+    let checks = readiness.checks.iter().map(|(name, check)| {
+        let check = if detailed { check.clone() } else { json!({ "status": check["status"] }) };
+        (name.to_string(), check)
+    });
+    let details = json!({
+        "checked_at": readiness.checked_at.to_rfc3339(),
+        "checks": checks.collect::<serde_json::Map<_, _>>(),
+    });
+    if failed.is_empty() {
+        return api::respond(Ok(CommandResponse::success("Ready.", Some(details))));
+    }
+    api::respond(Err(OmniError::NotReady { message: format!("Not ready: {} failed.", failed.join(", ")), details }))
+}
+
+async fn run_checks(app_state: &AppState) -> Readiness {
+    let (nlu, ipfs) = tokio::join!(check_nlu(app_state), check_ipfs());
+    Readiness {
+        at: Instant::now(),
+        checked_at: Local::now(),
+        checks: vec![("nlu", nlu), ("ipfs", ipfs), ("notes_dir", check_notes_dir()), ("config", check_config())],
+    }
+}
+
+// --- Checks ---
+// Each returns {"status": "ok" | "error" | "disabled", ...}; only "error" makes the core unready.
+
+async fn check_nlu(app_state: &AppState) -> serde_json::Value {
+    let Some(backend) = app_state.nlu() else {
+        return json!({ "status": "disabled", "message": "Keyword-only mode (nlu.backend = none)." });
+    };
+    let version = backend.version();
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, backend.ping()).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let Ok(mut last_ping) = LAST_NLU_PING.lock() else {
+        return json!({ "status": "error", "backend": backend.name(), "error": "NLU health state lock error" });
+    };
+    if last_ping.as_ref().is_some_and(|ping| ping.version != version) {
+        *last_ping = None;
+    }
+    let error = match result {
+        Ok(Ok(model)) => {
+            let ping = last_ping.insert(LastPing { version: version.clone(), model, at: Local::now() });
+            return json!({ "status": "ok", "backend": backend.name(), "version": version, "model": ping.model, "latency_ms": latency_ms, "last_ping_ok": ping.at.to_rfc3339() });
+        }
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("No answer within {} ms (the backend may be busy or still loading its model).", CHECK_TIMEOUT.as_millis()),
+    };
+    json!({
+        "status": "error",
+        "backend": backend.name(),
+        "version": version,
+        "model": last_ping.as_ref().and_then(|ping| ping.model.clone()),
+        "error": error,
+        "last_ping_ok": last_ping.as_ref().map(|ping| ping.at.to_rfc3339()),
+    })
+}
+
+async fn check_ipfs() -> serde_json::Value {
+    let api_url = crate::config::current().ipfs.api_url.clone();
+    let started = Instant::now();
+    match tokio::time::timeout(CHECK_TIMEOUT, commands::get_ipfs_id_for_api()).await {
+        Ok(Ok(_)) => json!({ "status": "ok", "api_url": api_url, "latency_ms": started.elapsed().as_millis() as u64 }),
+        Ok(Err(e)) => json!({ "status": "error", "api_url": api_url, "error": e.to_string() }),
+        Err(_) => json!({ "status": "error", "api_url": api_url, "error": format!("No answer from /id within {} ms.", CHECK_TIMEOUT.as_millis()) }),
+    }
+}
+
+// Creates the directory if needed (as create_note would), then writes and removes
+// a probe file that the notes watcher ignores.
+fn check_notes_dir() -> serde_json::Value {
+    let dir = commands::notes_dir();
+    let probe = dir.join(format!(".readyz-{}.tmp", uuid::Uuid::new_v4()));
+    let result = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&probe, b"")).and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => json!({ "status": "ok", "path": dir.display().to_string() }),
+        Err(e) => json!({ "status": "error", "path": dir.display().to_string(), "error": format!("Not writable: {}", e) }),
+    }
+}
+
+// The configuration in use was validated at startup or on reload; this reports a
+// file edited into an invalid state since, which the server is ignoring.
+fn check_config() -> serde_json::Value {
+    match reload::reload_error() {
+        None => json!({ "status": "ok" }),
+        Some(error) => json!({ "status": "error", "error": error, "message": "The config file no longer validates; the server keeps running on the last valid configuration." }),
+    }
+}
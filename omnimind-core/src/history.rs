@@ -0,0 +1,718 @@
+// src/history.rs for omnimind-core
+// The command history and audit log. Every executed command, whatever front end
+// it came from (POST /command, /ws, the REST endpoints, the terminal modes), is appended to
+// history.file as one JSON line: the raw text, what it resolved to, the outcome,
+// how long it took and who ran it. The file is rotated by size, keeping
+// history.max_files older files; `history` and `history search` read them back.
+//
+// The log is tamper-evident: each entry carries the hash of the one before it
+// and its own SHA-256, so editing, removing or reordering entries breaks the
+// chain (`verify_audit`). Rewriting the whole chain is caught by anchoring: the
+// server periodically adds the newest hash to IPFS (history.anchor_interval_secs)
+// and keeps the CIDs in history.anchors_file, and every anchored hash must still
+// be in the log.
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::auth::Principal;
+use crate::commands;
+use crate::config::{self, Config, HistoryConfig};
+use crate::error::{CommandResult, OmniError};
+use crate::events::{self, CoreEvent};
+use crate::nlu::NluResponse;
+use crate::progress::Progress;
+use crate::response::{CommandResponse, CommandStatus, ErrorCode};
+
+// Entries shown by a bare `history`.
+const DEFAULT_COUNT: usize = 20;
+// Most matches `history search` returns.
+const MAX_SEARCH_RESULTS: usize = 50;
+// prev_hash of the first entry ever written.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+// How much of the end of the file is read to find the newest entry.
+const TAIL_WINDOW: u64 = 64 * 1024;
+// Problems listed in a failed verification; the count covers all of them.
+const MAX_REPORTED_PROBLEMS: usize = 20;
+// How often the anchoring task looks again while anchoring is off.
+const ANCHOR_IDLE_CHECK: Duration = Duration::from_secs(10);
+
+
+// Resolution: What the dispatcher understood a command line as
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resolution {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>, // The command that ran, e.g. "create_note"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>, // Only when NLU, a taught phrase or a clarification chose the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>, // "command", "alias", "plan" or the NLU label, e.g. "keyword: list files"
+}
+
+impl Resolution {
+    // A command typed by name (or through an alias).
+    pub fn command(&mut self, intent: &str, arguments: &[String], resolved_by: &str) {
+        *self = Resolution {
+            intent: Some(intent.to_string()),
+            arguments: Some(arguments.join(" ")).filter(|arguments| !arguments.is_empty()),
+            confidence: None,
+            resolved_by: Some(resolved_by.to_string()),
+        };
+    }
+
+    // A command chosen by NLU, a taught phrase or a clarification answer.
+    pub fn nlu(&mut self, nlu_result: &NluResponse) {
+        *self = Resolution {
+            intent: Some(nlu_result.intent.clone()),
+            arguments: Some(nlu_result.arguments_text.clone()).filter(|arguments| !arguments.is_empty()),
+            confidence: Some(nlu_result.confidence),
+            resolved_by: Some(nlu_result.predicted_label.clone()),
+        };
+    }
+}
+
+// HistoryEntry: One executed command, as stored in the history file. Fields added
+// later must be optional and skipped when empty, or older entries stop verifying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: String, // RFC 3339, local time
+    pub session: String, // "http:<caller>" for POST /command, the session id for /ws, "rest", "repl", "run" or "script"
+    pub caller: String, // Token name, wallet address, "local" or "anonymous"
+    pub raw_command: String,
+    #[serde(flatten)]
+    pub resolution: Resolution,
+    pub status: CommandStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    pub message: String, // First line of the response message
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>, // `hash` of the entry before, GENESIS_HASH for the first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>, // SHA-256 (hex) of this entry serialized without `hash`
+}
+
+impl HistoryEntry {
+    // Case-insensitive match on what was typed, what it ran, who ran it and the outcome.
+    fn matches(&self, needle: &str) -> bool {
+        let fields = [Some(&self.raw_command), self.resolution.intent.as_ref(), self.resolution.arguments.as_ref(), Some(&self.caller), Some(&self.message)];
+        fields.into_iter().flatten().any(|field| field.to_lowercase().contains(needle))
+    }
+}
+
+// --- Writing ---
+
+// Message: Work for the writer thread
+enum Message {
+    Append(Arc<Config>, Box<HistoryEntry>),
+    Written(tokio::sync::oneshot::Sender<()>), // Answered once everything sent before it is written
+}
+
+static WRITER: OnceLock<mpsc::Sender<Message>> = OnceLock::new();
+
+// Appends run on one thread of their own, so a slow disk or another process
+// holding the lock never stalls the request that recorded the command.
+fn writer() -> &'static mpsc::Sender<Message> {
+    WRITER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut writer = Writer::default();
+            for message in receiver {
+                match message {
+                    Message::Append(config, entry) => {
+                        if let Err(e) = writer.append(&config.history, *entry) {
+                            tracing::warn!("Could not write history to '{}': {}", config.history.file.display(), e);
+                        }
+                    }
+                    Message::Written(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        sender
+    })
+}
+
+// Waits until the entries recorded so far are written (or have failed to be):
+// before reading the history back, and before a terminal mode exits.
+pub async fn written() {
+    let Some(writer) = WRITER.get() else { return };
+    let (done, wait) = tokio::sync::oneshot::channel();
+    if writer.send(Message::Written(done)).is_ok() {
+        let _ = wait.await;
+    }
+}
+
+// Appends one executed command to the history. A write failure is logged, not
+// returned: the command has already run.
+pub fn record(session: &str, principal: &Principal, raw_command: &str, resolution: Resolution, response: &CommandResponse, duration: Duration) {
+    let config = config::current();
+    if !config.history.enabled {
+        return;
+    }
+    let entry = HistoryEntry {
+        time: chrono::Local::now().to_rfc3339(),
+        session: session.to_string(),
+        caller: principal.name.clone(),
+        raw_command: raw_command.trim().to_string(),
+        resolution,
+        status: response.status,
+        code: response.code,
+        message: response.message.lines().next().unwrap_or("").to_string(),
+        duration_ms: duration.as_millis().try_into().unwrap_or(u64::MAX),
+        prev_hash: None,
+        hash: None,
+    };
+    let _ = writer().send(Message::Append(config, Box::new(entry)));
+}
+
+// Writer: Appends entries, remembering the chain head it last wrote
+#[derive(Default)]
+struct Writer {
+    head: Option<Head>,
+}
+
+// Head: The newest entry's hash, and the file as writing it left it
+struct Head {
+    file: PathBuf,
+    hash: String,
+    len: u64,
+    modified: SystemTime,
+}
+
+impl Writer {
+    fn append(&mut self, config: &HistoryConfig, mut entry: HistoryEntry) -> io::Result<()> {
+        if let Some(parent) = config.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _lock = lock_for_writing(config)?;
+        // Processes sharing the file (server, `run`) extend one chain: the tail is
+        // read again whenever someone else has written since.
+        let prev_hash = match self.head.take().filter(|head| head.is_current(config)) {
+            Some(head) => head.hash,
+            None => newest_entry(config)?.and_then(|newest| newest.hash).unwrap_or_else(|| GENESIS_HASH.to_string()),
+        };
+        entry.prev_hash = Some(prev_hash);
+        let hash = entry_hash(&entry);
+        entry.hash = Some(hash.clone());
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let size = fs::metadata(&config.file).map(|metadata| metadata.len()).unwrap_or(0);
+        if config.max_file_bytes > 0 && size > 0 && size + line.len() as u64 > config.max_file_bytes {
+            rotate(config)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&config.file)?;
+        file.write_all(line.as_bytes())?;
+        let metadata = file.metadata()?;
+        self.head = Some(Head { file: config.file.clone(), hash, len: metadata.len(), modified: metadata.modified()? });
+        Ok(())
+    }
+}
+
+impl Head {
+    fn is_current(&self, config: &HistoryConfig) -> bool {
+        let Ok(metadata) = fs::metadata(&config.file) else { return false };
+        self.file == config.file && metadata.len() == self.len && metadata.modified().ok() == Some(self.modified)
+    }
+}
+
+// Takes an exclusive lock on <file>.lock, released when the returned handle is
+// dropped. It serializes appends and rotation across threads and across every
+// process writing the history, so no two of them chain onto the same head. The
+// lock lives in its own file because rotation renames the log.
+fn lock_for_writing(config: &HistoryConfig) -> io::Result<File> {
+    let mut name = config.file.as_os_str().to_owned();
+    name.push(".lock");
+    let lock = OpenOptions::new().create(true).truncate(false).write(true).open(PathBuf::from(name))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+// file.1 becomes file.2 and so on, the file itself becomes file.1, and whatever
+// would become file.<max_files + 1> is deleted.
+fn rotate(config: &HistoryConfig) -> io::Result<()> {
+    if config.max_files == 0 {
+        return fs::remove_file(&config.file);
+    }
+    match fs::remove_file(rotated(&config.file, config.max_files)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    for n in (1..config.max_files).rev() {
+        let from = rotated(&config.file, n);
+        if from.exists() {
+            fs::rename(&from, rotated(&config.file, n + 1))?;
+        }
+    }
+    fs::rename(&config.file, rotated(&config.file, 1))
+}
+
+fn rotated(file: &Path, n: usize) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+fn entry_hash(entry: &HistoryEntry) -> String {
+    let unhashed = HistoryEntry { hash: None, ..entry.clone() };
+    hex::encode(Sha256::digest(serde_json::to_vec(&unhashed).unwrap_or_default()))
+}
+
+// The newest entry: at the end of the file or, right after a rotation, of file.1.
+fn newest_entry(config: &HistoryConfig) -> io::Result<Option<HistoryEntry>> {
+    for file in [config.file.clone(), rotated(&config.file, 1)] {
+        if let Some(entry) = last_entry_in(&file)? {
+            return Ok(Some(entry));
+        }
+    }
+    Ok(None)
+}
+
+// Reads backwards from the end in growing windows, so long files are not read whole.
+fn last_entry_in(file: &Path) -> io::Result<Option<HistoryEntry>> {
+    let mut handle = match File::open(file) {
+        Ok(handle) => handle,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let len = handle.metadata()?.len();
+    let mut window = TAIL_WINDOW.min(len);
+    loop {
+        handle.seek(SeekFrom::Start(len - window))?;
+        let mut tail = Vec::new();
+        handle.read_to_end(&mut tail)?;
+        let text = String::from_utf8_lossy(&tail);
+        // The first line of a partial window may start mid-entry.
+        let skip = usize::from(window < len);
+        if let Some(entry) = text.lines().skip(skip).collect::<Vec<_>>().into_iter().rev().find_map(|line| serde_json::from_str(line).ok()) {
+            return Ok(Some(entry));
+        }
+        if window == len {
+            return Ok(None);
+        }
+        window = (window * 4).min(len);
+    }
+}
+
+// The history files that still exist, oldest first.
+fn files_oldest_first(config: &HistoryConfig) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (1..=config.max_files).rev().map(|n| rotated(&config.file, n)).collect();
+    files.push(config.file.clone());
+    files.into_iter().filter(|file| file.exists()).collect()
+}
+
+// Entries newest first, across the current and rotated files, up to `limit`.
+fn read_newest(config: &HistoryConfig, limit: usize, wanted: impl Fn(&HistoryEntry) -> bool) -> Result<Vec<HistoryEntry>, OmniError> {
+    let mut entries = Vec::new();
+    let files = std::iter::once(config.file.clone()).chain((1..=config.max_files).map(|n| rotated(&config.file, n)));
+    for file in files {
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(OmniError::io(format!("Failed to read history file '{}'", file.display()), e)),
+        };
+        // A line cut short by a crash mid-write is skipped.
+        for entry in text.lines().rev().filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok()) {
+            if wanted(&entry) {
+                entries.push(entry);
+                if entries.len() == limit {
+                    return Ok(entries);
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// history [count]  |  history search <text>
+pub async fn history_for_api(args_str: &[String]) -> CommandResult {
+    written().await;
+    let config = config::current();
+    let usage = || OmniError::InvalidArguments("Usage: history [count] | history search <text>".to_string());
+    let (message, entries) = match args_str.first().map(|arg| arg.to_lowercase()).as_deref() {
+        Some("search") => {
+            let text = args_str[1..].join(" ");
+            if text.is_empty() {
+                return Err(usage());
+            }
+            let needle = text.to_lowercase();
+            let entries = read_newest(&config.history, MAX_SEARCH_RESULTS, |entry| entry.matches(&needle))?;
+            (format!("{} command(s) matching '{}', newest first:", entries.len(), text), entries)
+        }
+        count => {
+            let count = match count {
+                None => DEFAULT_COUNT,
+                Some(count) => count.parse::<usize>().ok().filter(|count| *count > 0).ok_or_else(usage)?,
+            };
+            let entries = read_newest(&config.history, count, |_| true)?;
+            (format!("Last {} command(s), newest first:", entries.len()), entries)
+        }
+    };
+    // The hashes are for verify_audit; listings leave them out.
+    let entries: Vec<HistoryEntry> = entries.into_iter().map(|entry| HistoryEntry { prev_hash: None, hash: None, ..entry }).collect();
+    if entries.is_empty() {
+        let message = if config.history.enabled { "No matching commands in the history." } else { "No matching commands; recording is off (history.enabled = false)." };
+        return Ok(CommandResponse::info(message));
+    }
+    Ok(CommandResponse::success(message, Some(serde_json::json!(entries))))
+}
+
+// --- Verification ---
+
+// Anchor: One chain head added to IPFS, as kept in history.anchors_file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anchor {
+    pub anchored_at: String,
+    pub head_hash: String, // `hash` of the newest entry at the time
+    pub head_time: String, // Its `time`, to tell an anchor rotated out of the log from a missing one
+    pub cid: String,
+}
+
+// AnchorDocument: What is added to IPFS for an anchor
+#[derive(Debug, Serialize, Deserialize)]
+struct AnchorDocument {
+    #[serde(rename = "type")]
+    kind: String, // Always "omnimind_audit_anchor"
+    head_hash: String,
+    head_time: String,
+    anchored_at: String,
+}
+
+// verify_audit: recomputes every hash and link of the chain, checks that every
+// anchored hash is still in it, and compares the newest anchor with its IPFS copy.
+pub async fn verify_audit_for_api() -> CommandResult {
+    written().await;
+    let config = config::current().history.clone();
+    verify_audit(&config).await
+}
+
+async fn verify_audit(config: &HistoryConfig) -> CommandResult {
+    let mut problems = Vec::new();
+    let (mut entries, mut legacy) = (0, 0);
+    let mut expected_prev: Option<String> = None; // None until the first chained entry
+    let mut starts_after_rotation = false;
+    let mut first_time: Option<DateTime<FixedOffset>> = None;
+    let mut hashes = HashSet::new();
+
+    let files = files_oldest_first(config);
+    for file in &files {
+        let text = fs::read_to_string(file).map_err(|e| OmniError::io(format!("Failed to read history file '{}'", file.display()), e))?;
+        let complete = text.ends_with('\n') || text.is_empty();
+        let lines: Vec<&str> = text.lines().collect();
+        for (index, line) in lines.iter().enumerate() {
+            let at = format!("{}:{}", file.display(), index + 1);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match serde_json::from_str::<HistoryEntry>(line) {
+                Ok(entry) => entry,
+                // The last line of the current file may be a write in progress.
+                Err(_) if !complete && index + 1 == lines.len() && *file == config.file => continue,
+                Err(e) => {
+                    problems.push(format!("{}: unreadable entry ({})", at, e));
+                    continue;
+                }
+            };
+            entries += 1;
+            if first_time.is_none() {
+                first_time = DateTime::parse_from_rfc3339(&entry.time).ok();
+            }
+            let (Some(prev_hash), Some(hash)) = (&entry.prev_hash, &entry.hash) else {
+                // Entries written before chaining existed come first; any later one was inserted.
+                if expected_prev.is_some() {
+                    problems.push(format!("{}: entry has no hash (inserted after chaining started)", at));
+                } else {
+                    legacy += 1;
+                }
+                continue;
+            };
+            if entry_hash(&entry) != *hash {
+                problems.push(format!("{}: contents do not match the entry's hash (modified)", at));
+            } else if serde_json::to_string(&entry).ok().as_deref() != Some(line) {
+                problems.push(format!("{}: entry was rewritten (extra or reformatted fields)", at));
+            }
+            match &expected_prev {
+                Some(expected) if expected != prev_hash => problems.push(format!("{}: previous hash does not match the entry before it (entries removed, inserted or reordered)", at)),
+                None if prev_hash != GENESIS_HASH => starts_after_rotation = true,
+                _ => {}
+            }
+            expected_prev = Some(hash.clone());
+            hashes.insert(hash.clone());
+        }
+    }
+
+    // Anchors: a rewritten chain has new hashes, so anchored ones go missing.
+    let anchors = read_anchors(config)?;
+    let mut anchors_confirmed = 0;
+    for anchor in &anchors {
+        if hashes.contains(&anchor.head_hash) {
+            anchors_confirmed += 1;
+        } else if !rotated_out(anchor, first_time) {
+            problems.push(format!("anchored entry {} (CID {}, {}) is missing from the log", short(&anchor.head_hash), anchor.cid, anchor.head_time));
+        }
+    }
+    let ipfs_check = match anchors.last() {
+        None => serde_json::json!(null),
+        Some(anchor) => match fetch_anchor(&anchor.cid).await {
+            Ok(document) if document.head_hash == anchor.head_hash => serde_json::json!({ "cid": anchor.cid, "matches": true }),
+            Ok(document) => {
+                problems.push(format!("anchors file says CID {} anchors {}, but IPFS has {}", anchor.cid, short(&anchor.head_hash), short(&document.head_hash)));
+                serde_json::json!({ "cid": anchor.cid, "matches": false })
+            }
+            // An unreachable IPFS node leaves the anchors unchecked, not the log broken.
+            Err(e) => serde_json::json!({ "cid": anchor.cid, "matches": null, "error": e.to_string() }),
+        },
+    };
+
+    let details = serde_json::json!({
+        "files": files.iter().map(|file| file.display().to_string()).collect::<Vec<_>>(),
+        "entries": entries,
+        "unchained_entries": legacy,
+        "starts_after_rotation": starts_after_rotation,
+        "head_hash": expected_prev,
+        "anchors": anchors.len(),
+        "anchors_confirmed": anchors_confirmed,
+        "latest_anchor_on_ipfs": ipfs_check,
+    });
+    if !problems.is_empty() {
+        let mut details = details;
+        details["problem_count"] = serde_json::json!(problems.len());
+        problems.truncate(MAX_REPORTED_PROBLEMS);
+        details["problems"] = serde_json::json!(problems);
+        return Err(OmniError::AuditTampered { message: format!("Audit log verification failed: {} problem(s) found.", details["problem_count"]), details });
+    }
+    Ok(CommandResponse::success(
+        format!("Audit log intact: {} entries in {} file(s), {} of {} anchor(s) confirmed.", entries, files.len(), anchors_confirmed, anchors.len()),
+        Some(details),
+    ))
+}
+
+// Whether an anchor missing from the log is older than anything still kept in it.
+// Times are compared as instants: entries keep the local offset they were written with.
+fn rotated_out(anchor: &Anchor, first_time: Option<DateTime<FixedOffset>>) -> bool {
+    first_time.is_none_or(|first| DateTime::parse_from_rfc3339(&anchor.head_time).is_ok_and(|anchored| anchored < first))
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+fn read_anchors(config: &HistoryConfig) -> Result<Vec<Anchor>, OmniError> {
+    match fs::read_to_string(&config.anchors_file) {
+        Ok(text) => Ok(text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(OmniError::io(format!("Failed to read anchors file '{}'", config.anchors_file.display()), e)),
+    }
+}
+
+async fn fetch_anchor(cid: &str) -> Result<AnchorDocument, OmniError> {
+    let response = commands::cat_file_from_ipfs_for_api(cid, &Progress::none()).await?;
+    let content = response.data.as_ref().and_then(|data| data["content"].as_str()).unwrap_or_default();
+    serde_json::from_str(content).map_err(|e| OmniError::Ipfs(format!("CID {} is not an audit anchor: {}", cid, e)))
+}
+
+// --- Anchoring ---
+
+// Runs for the lifetime of the server, anchoring the chain head every
+// history.anchor_interval_secs (0 turns it off) when it has changed.
+pub async fn anchor_periodically() {
+    loop {
+        let interval = config::current().history.anchor_interval_secs;
+        if interval == 0 {
+            tokio::time::sleep(ANCHOR_IDLE_CHECK).await;
+            continue;
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        match anchor_head().await {
+            Ok(Some(anchor)) => tracing::info!("Anchored audit log head {} to IPFS as {}.", short(&anchor.head_hash), anchor.cid),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Could not anchor the audit log to IPFS (will retry): {}", e),
+        }
+    }
+}
+
+// Adds the newest hash to IPFS unless it is already the latest anchor.
+async fn anchor_head() -> Result<Option<Anchor>, OmniError> {
+    let config = config::current().history.clone();
+    let head = newest_entry(&config).map_err(|e| OmniError::io(format!("Failed to read history file '{}'", config.file.display()), e))?;
+    let Some((head_hash, head_time)) = head.and_then(|entry| Some((entry.hash?, entry.time))) else { return Ok(None) };
+    if read_anchors(&config)?.last().is_some_and(|anchor| anchor.head_hash == head_hash) {
+        return Ok(None);
+    }
+
+    let anchored_at = chrono::Local::now().to_rfc3339();
+    let document = AnchorDocument { kind: "omnimind_audit_anchor".to_string(), head_hash: head_hash.clone(), head_time: head_time.clone(), anchored_at: anchored_at.clone() };
+    let bytes = serde_json::to_vec_pretty(&document).map_err(|e| OmniError::Internal(format!("Failed to encode audit anchor: {}", e)))?;
+    let response = commands::add_bytes_to_ipfs_for_api(format!("omnimind-audit-{}.json", short(&head_hash)), bytes.into(), &Progress::none()).await?;
+    let cid = response.data.as_ref().and_then(|data| data["cid"].as_str()).map(String::from).ok_or_else(|| OmniError::Ipfs("IPFS add returned no CID for the audit anchor.".to_string()))?;
+
+    let anchor = Anchor { anchored_at, head_hash, head_time, cid };
+    let mut line = serde_json::to_string(&anchor).map_err(|e| OmniError::Internal(format!("Failed to encode audit anchor: {}", e)))?;
+    line.push('\n');
+    let path = &config.anchors_file;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| OmniError::io(format!("Failed to create directory for '{}'", path.display()), e))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| OmniError::io(format!("Failed to write anchors file '{}'", path.display()), e))?;
+    events::publish(CoreEvent::AuditAnchored { cid: anchor.cid.clone(), head_hash: anchor.head_hash.clone() });
+    Ok(Some(anchor))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    // A history kept in a scratch directory, removed with the TempDir.
+    fn test_config(max_file_bytes: u64) -> (TempDir, HistoryConfig) {
+        let dir = TempDir::new().unwrap();
+        let config = HistoryConfig {
+            enabled: true,
+            file: dir.path().join("history.jsonl"),
+            max_file_bytes,
+            max_files: 5,
+            anchor_interval_secs: 0,
+            anchors_file: dir.path().join("history_anchors.jsonl"),
+        };
+        (dir, config)
+    }
+
+    fn entry(raw_command: &str) -> HistoryEntry {
+        HistoryEntry {
+            time: chrono::Local::now().to_rfc3339(),
+            session: "test".to_string(),
+            caller: "local".to_string(),
+            raw_command: raw_command.to_string(),
+            resolution: Resolution::default(),
+            status: CommandStatus::Success,
+            code: None,
+            message: format!("ran {}", raw_command),
+            duration_ms: 1,
+            prev_hash: None,
+            hash: None,
+        }
+    }
+
+    fn write_entries(config: &HistoryConfig, count: usize) {
+        let mut writer = Writer::default();
+        for n in 0..count {
+            writer.append(config, entry(&format!("echo {}", n))).unwrap();
+        }
+    }
+
+    fn problems(result: CommandResult) -> Vec<String> {
+        match result {
+            Err(OmniError::AuditTampered { details, .. }) => serde_json::from_value(details["problems"].clone()).unwrap(),
+            other => panic!("expected audit_tampered, got {:?}", other.map(|response| response.message)),
+        }
+    }
+
+    #[test]
+    fn appends_link_each_entry_to_the_one_before() {
+        let (_dir, config) = test_config(0);
+        write_entries(&config, 3);
+        let text = fs::read_to_string(&config.file).unwrap();
+        let entries: Vec<HistoryEntry> = text.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries[0].prev_hash.as_deref(), Some(GENESIS_HASH));
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(entries[2].prev_hash, entries[1].hash);
+        assert_eq!(entries[2].hash.as_deref(), Some(entry_hash(&entries[2]).as_str()));
+    }
+
+    #[tokio::test]
+    async fn writers_in_different_processes_extend_one_chain() {
+        let (_dir, config) = test_config(0);
+        // Each Writer stands for a process that does not see the other's appends.
+        let (mut server, mut run) = (Writer::default(), Writer::default());
+        server.append(&config, entry("echo 1")).unwrap();
+        run.append(&config, entry("echo 2")).unwrap();
+        server.append(&config, entry("echo 3")).unwrap();
+        let response = verify_audit(&config).await.unwrap();
+        assert_eq!(response.data.unwrap()["entries"], 3);
+    }
+
+    #[tokio::test]
+    async fn recorded_commands_reach_the_file_through_the_writer_thread() {
+        let (_dir, history) = test_config(0);
+        let _config = config::testing::install(config::Config { history: history.clone(), ..config::Config::default() }).await;
+        let response = CommandResponse::success("hi", None);
+        record("test", &Principal::local(), "echo hi", Resolution::default(), &response, Duration::from_millis(1));
+        written().await;
+        let newest = newest_entry(&history).unwrap().unwrap();
+        assert_eq!(newest.raw_command, "echo hi");
+        assert_eq!(newest.prev_hash.as_deref(), Some(GENESIS_HASH));
+    }
+
+    #[tokio::test]
+    async fn an_untouched_log_verifies() {
+        let (_dir, config) = test_config(0);
+        write_entries(&config, 3);
+        let response = verify_audit(&config).await.unwrap();
+        assert_eq!(response.data.unwrap()["entries"], 3);
+    }
+
+    #[tokio::test]
+    async fn the_chain_continues_across_rotated_files() {
+        let (_dir, config) = test_config(1200);
+        write_entries(&config, 8);
+        assert!(rotated(&config.file, 1).exists());
+        let response = verify_audit(&config).await.unwrap();
+        let data = response.data.unwrap();
+        assert_eq!(data["entries"], 8);
+        assert_eq!(data["starts_after_rotation"], false);
+    }
+
+    #[tokio::test]
+    async fn an_edited_entry_is_reported() {
+        let (_dir, config) = test_config(0);
+        write_entries(&config, 3);
+        let text = fs::read_to_string(&config.file).unwrap().replace("ran echo 1", "ran echo 42");
+        fs::write(&config.file, text).unwrap();
+        let problems = problems(verify_audit(&config).await);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].ends_with(":2: contents do not match the entry's hash (modified)"), "{:?}", problems);
+    }
+
+    #[tokio::test]
+    async fn a_removed_entry_breaks_the_link() {
+        let (_dir, config) = test_config(0);
+        write_entries(&config, 3);
+        let text = fs::read_to_string(&config.file).unwrap();
+        let kept: Vec<&str> = text.lines().enumerate().filter(|(index, _)| *index != 1).map(|(_, line)| line).collect();
+        fs::write(&config.file, kept.join("\n") + "\n").unwrap();
+        let problems = problems(verify_audit(&config).await);
+        assert!(problems[0].contains("previous hash does not match"), "{:?}", problems);
+    }
+
+    #[test]
+    fn anchor_age_is_compared_as_an_instant() {
+        let anchor = |head_time: &str| Anchor {
+            anchored_at: head_time.to_string(),
+            head_hash: "ab".repeat(32),
+            head_time: head_time.to_string(),
+            cid: "QmAnchor".to_string(),
+        };
+        // 01:00 UTC, written while the local offset was +02:00.
+        let first = DateTime::parse_from_rfc3339("2026-03-29T03:00:00+02:00").ok();
+        // Sorts before the first entry as text, but is half an hour later.
+        assert!(!rotated_out(&anchor("2026-03-29T01:30:00+00:00"), first));
+        assert!(rotated_out(&anchor("2026-03-29T00:30:00+00:00"), first));
+        assert!(rotated_out(&anchor("2026-03-29T01:30:00+00:00"), None));
+    }
+}
@@ -0,0 +1,260 @@
+// src/limits.rs for omnimind-core
+// Guards against a single client tying up the server ([limits], applied live on
+// reload; 0 turns a limit off). Each has its own error code:
+// - rate_limited (429): a token bucket per API token, or per IP address for
+//   anonymous callers, charged for every HTTP request and every WebSocket command;
+//   the response carries Retry-After. Requests turned away for a missing or
+//   invalid token are charged to their IP address, so guessing tokens costs too.
+// - command_too_long (413): raw_command from POST /command and /ws.
+// - upload_too_large (413): files read by ipfs_add and POST /ipfs/add.
+// - nlu_busy (503): NLU requests beyond nlu_max_concurrent, so a flood of free-form
+//   commands cannot queue up behind the single Python NLU pipe.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderValue, RETRY_AFTER};
+use actix_web::middleware::Next;
+use actix_web::{HttpMessage, HttpResponse};
+
+use crate::api;
+use crate::auth::{self, Principal};
+use crate::config;
+use crate::error::OmniError;
+use crate::nlu::NluError;
+
+// Above this many tracked callers, buckets that have refilled are dropped.
+const MAX_TRACKED_CALLERS: usize = 10_000;
+
+// Liveness probes and scrapes poll on a schedule and must not be turned away;
+// both are cheap. /readyz is charged: it pings the NLU backend.
+const EXEMPT_PATHS: &[&str] = &["/healthz", "/metrics"];
+
+// --- Rate limit ---
+
+// Bucket: Requests a caller may still make, refilled continuously
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    // Refills for the time since the last request, then takes one; Err is the
+    // number of seconds until one is available.
+    fn take(&mut self, now: Instant, per_second: f64, capacity: f64) -> Result<(), u64> {
+        self.tokens = (self.tokens + now.duration_since(self.updated).as_secs_f64() * per_second).min(capacity);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(((1.0 - self.tokens) / per_second).ceil().max(1.0) as u64)
+    }
+}
+
+fn buckets() -> &'static Mutex<HashMap<String, Bucket>> {
+    static BUCKETS: OnceLock<Mutex<HashMap<String, Bucket>>> = OnceLock::new();
+    BUCKETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Takes one request from the caller's bucket.
+pub fn check_rate(key: &str) -> Result<(), OmniError> {
+    let (per_minute, burst) = {
+        let limits = &config::current().limits;
+        (limits.rate_limit_per_minute, limits.rate_limit_burst)
+    };
+    if per_minute == 0 {
+        return Ok(());
+    }
+    let per_second = f64::from(per_minute) / 60.0;
+    let capacity = f64::from(burst.max(1));
+    let now = Instant::now();
+
+    let mut buckets = buckets().lock().map_err(|_| OmniError::Internal("Rate limiter lock error".to_string()))?;
+    if buckets.len() >= MAX_TRACKED_CALLERS {
+        buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_second < capacity);
+    }
+    let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+    bucket.take(now, per_second, capacity).map_err(|retry_after_secs| {
+        tracing::debug!(caller = key, retry_after_secs, "rate limited");
+        OmniError::RateLimited { retry_after_secs }
+    })
+}
+
+// Middleware: charges each request to its caller. Registered inside the auth
+// middleware, so the principal is known; requests that fail authentication are
+// charged there instead (see `auth::require_token`).
+pub async fn rate_limit(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if !EXEMPT_PATHS.contains(&req.path()) {
+        let principal = req.extensions().get::<Principal>().cloned().unwrap_or_else(Principal::anonymous);
+        if let Err(error) = check_rate(&auth::caller_key(&principal, req.peer_addr())) {
+            return Ok(req.into_response(rate_limited(error)));
+        }
+    }
+    Ok(next.call(req).await?.map_into_boxed_body())
+}
+
+// The 429 answer for a check_rate failure, with Retry-After.
+pub fn rate_limited(error: OmniError) -> HttpResponse {
+    let retry_after = match &error {
+        OmniError::RateLimited { retry_after_secs } => HeaderValue::from(*retry_after_secs),
+        _ => HeaderValue::from_static("1"),
+    };
+    let mut response = api::respond(Err(error));
+    response.headers_mut().insert(RETRY_AFTER, retry_after);
+    response
+}
+
+// --- Sizes ---
+
+pub fn check_command_length(raw_command: &str) -> Result<(), OmniError> {
+    let max = config::current().limits.max_command_length;
+    if max > 0 && raw_command.len() > max {
+        return Err(OmniError::CommandTooLong(format!("Command is {} bytes; the limit is {} (limits.max_command_length).", raw_command.len(), max)));
+    }
+    Ok(())
+}
+
+// `size` is the file's length, or the bytes received so far for a streamed upload.
+pub fn check_upload_size(size: u64) -> Result<(), OmniError> {
+    let max = config::current().limits.max_upload_bytes;
+    if max > 0 && size > max {
+        return Err(OmniError::UploadTooLarge(format!("The file is larger than the {}-byte upload limit (limits.max_upload_bytes).", max)));
+    }
+    Ok(())
+}
+
+// --- NLU concurrency ---
+
+static NLU_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+// NluPermit: One NLU request in flight, released when dropped
+pub struct NluPermit(());
+
+impl NluPermit {
+    // Fails at once rather than queueing, so callers see the backlog instead of a timeout.
+    pub fn acquire() -> Result<Self, NluError> {
+        let max = config::current().limits.nlu_max_concurrent;
+        let previous = NLU_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        if max > 0 && previous >= max {
+            NLU_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+            return Err(NluError::Busy(format!("The NLU service is busy ({} concurrent requests, limits.nlu_max_concurrent); try again shortly.", max)));
+        }
+        Ok(NluPermit(()))
+    }
+}
+
+impl Drop for NluPermit {
+    fn drop(&mut self) {
+        NLU_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::StatusCode;
+    use actix_web::middleware::from_fn;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::{web, App};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::testing::ConfigGuard;
+    use crate::config::{Config, LimitsConfig};
+
+    // Installs `limits` with no API tokens, so every caller is charged by address.
+    async fn install_limits(limits: LimitsConfig) -> (web::Data<crate::AppState>, TempDir, ConfigGuard) {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config { limits, ..Config::default() };
+        config.nlu.backend = "none".to_string();
+        config.storage.tokens_file = dir.path().join("tokens.json");
+        config.storage.corrections_file = dir.path().join("corrections.json");
+        config.history.enabled = false;
+        let guard = crate::config::testing::install(config.clone()).await;
+        (web::Data::new(crate::build_app_state(&config).unwrap()), dir, guard)
+    }
+
+    // Sends `request` through the middleware stack the server uses.
+    async fn call(app_state: &web::Data<crate::AppState>, request: TestRequest) -> ServiceResponse {
+        let app = init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .wrap(from_fn(rate_limit))
+                .wrap(from_fn(auth::require_token))
+                .configure(crate::configure_routes),
+        )
+        .await;
+        call_service(&app, request.to_request()).await
+    }
+
+    #[test]
+    fn a_bucket_allows_a_burst_then_refills_at_the_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket { tokens: 2.0, updated: start };
+        // 60 a minute: one a second.
+        assert_eq!(bucket.take(start, 1.0, 2.0), Ok(()));
+        assert_eq!(bucket.take(start, 1.0, 2.0), Ok(()));
+        assert_eq!(bucket.take(start, 1.0, 2.0), Err(1));
+        assert_eq!(bucket.take(start + Duration::from_millis(500), 1.0, 2.0), Err(1));
+        assert_eq!(bucket.take(start + Duration::from_millis(1000), 1.0, 2.0), Ok(()));
+        // A long pause refills no more than the burst.
+        let later = start + Duration::from_secs(3600);
+        assert_eq!(bucket.take(later, 1.0, 2.0), Ok(()));
+        assert_eq!(bucket.take(later, 1.0, 2.0), Ok(()));
+        assert!(bucket.take(later, 1.0, 2.0).is_err());
+    }
+
+    #[test]
+    fn retry_after_counts_the_seconds_until_the_next_token() {
+        let start = Instant::now();
+        // 6 a minute: one every 10 seconds.
+        let mut bucket = Bucket { tokens: 0.0, updated: start };
+        assert_eq!(bucket.take(start, 0.1, 1.0), Err(10));
+        assert_eq!(bucket.take(start + Duration::from_secs(4), 0.1, 1.0), Err(6));
+    }
+
+    #[tokio::test]
+    async fn nlu_permits_are_refused_at_the_cap_and_released_on_drop() {
+        let (_app_state, _dir, _config) = install_limits(LimitsConfig { nlu_max_concurrent: 2, ..LimitsConfig::default() }).await;
+        let first = NluPermit::acquire().unwrap();
+        let _second = NluPermit::acquire().unwrap();
+        assert!(matches!(NluPermit::acquire(), Err(NluError::Busy(_))));
+        drop(first);
+        assert!(NluPermit::acquire().is_ok());
+    }
+
+    #[actix_web::test]
+    async fn callers_over_the_rate_get_429_with_retry_after_except_on_exempt_paths() {
+        let limits = LimitsConfig { rate_limit_per_minute: 1, rate_limit_burst: 1, ..LimitsConfig::default() };
+        let (app_state, _dir, _config) = install_limits(limits).await;
+        let from = |uri: &str, peer: &str| TestRequest::get().uri(uri).peer_addr(peer.parse().unwrap());
+
+        assert_eq!(call(&app_state, from("/openapi.json", "192.0.2.1:4000")).await.status(), StatusCode::OK);
+        let limited = call(&app_state, from("/openapi.json", "192.0.2.1:4001")).await;
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers().get(RETRY_AFTER).unwrap(), "60");
+        // Another address has its own bucket.
+        assert_eq!(call(&app_state, from("/openapi.json", "192.0.2.2:4000")).await.status(), StatusCode::OK);
+        for _ in 0..3 {
+            assert_eq!(call(&app_state, from("/healthz", "192.0.2.1:4002")).await.status(), StatusCode::OK);
+        }
+    }
+
+    #[actix_web::test]
+    async fn an_overlong_command_gets_413() {
+        let (app_state, _dir, _config) = install_limits(LimitsConfig { max_command_length: 16, ..LimitsConfig::default() }).await;
+        let command = |raw_command: &str| TestRequest::post().uri("/command").peer_addr("192.0.2.3:4000".parse().unwrap()).set_json(serde_json::json!({ "raw_command": raw_command }));
+
+        assert_eq!(call(&app_state, command("echo short")).await.status(), StatusCode::OK);
+        let response = call(&app_state, command("echo this is far too long")).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = read_body_json(response).await;
+        assert_eq!(body["code"], "command_too_long");
+    }
+}
@@ -0,0 +1,111 @@
+// src/logging.rs for omnimind-core
+// Diagnostics go through `tracing` to standard error, as text or as JSON lines
+// (log.format). log.level takes EnvFilter directives, e.g. "info" or
+// "info,omnimind_core::nlu=debug,nlu=warn", and applies again on hot reload.
+// Every HTTP request runs in a `request` span with a request ID (the caller's
+// X-Request-Id, or a new UUID) that is echoed in the response; every command runs
+// in a `command` span, and its request ID is passed on to the NLU backend. The
+// Python NLU script's standard error is logged under the `nlu` target.
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::config::LogConfig;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// Longest caller-supplied request ID we pass through; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    // The request ID of the request or command running on this task.
+    static REQUEST_ID: String;
+}
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+// Installs the global subscriber. `level` replaces log.level, for the terminal
+// modes that keep their output clean unless a level was asked for.
+pub fn init(config: &LogConfig, level: Option<&str>) {
+    let filter = EnvFilter::try_new(level.unwrap_or(&config.level)).unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+    let registry = tracing_subscriber::registry().with(filter);
+    let installed = if config.format == "json" {
+        registry.with(fmt::layer().json().flatten_event(true).with_writer(std::io::stderr)).try_init()
+    } else {
+        registry.with(fmt::layer().with_ansi(std::io::stderr().is_terminal()).with_writer(std::io::stderr)).try_init()
+    };
+    if installed.is_ok() {
+        let _ = FILTER.set(handle);
+    }
+}
+
+// Applies a new log.level (config reload).
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(level).map_err(|e| e.to_string())?;
+    match FILTER.get() {
+        Some(handle) => handle.reload(filter).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+// Checks log.level directives without applying them (config validation).
+pub fn check_level(level: &str) -> Result<(), String> {
+    EnvFilter::try_new(level).map(|_| ()).map_err(|e| e.to_string())
+}
+
+// --- Request IDs ---
+
+pub fn new_request_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// The request ID of the request or command this task is running, if any.
+pub fn request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+// Runs `future` with `request_id` as the task's request ID.
+pub async fn with_request_id<F: std::future::Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+// Caller-supplied IDs are kept only if they are short and safe to log and echo.
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.chars().all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+}
+
+// Middleware: runs each request in a `request` span under its request ID, sets
+// X-Request-Id on the response and logs the outcome. Outside the auth
+// middleware, so rejected requests are logged too.
+pub async fn trace_request(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let request_id = req.headers().get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map_or_else(new_request_id, String::from);
+    let is_probe = matches!(req.path(), "/healthz" | "/readyz");
+    let span = tracing::info_span!("request", request_id = %request_id, method = %req.method(), path = %req.path());
+    let started = Instant::now();
+    let mut response = with_request_id(request_id.clone(), next.call(req).instrument(span.clone())).await?;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    let status = response.status().as_u16();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    // Orchestrators poll the probes every few seconds.
+    if is_probe {
+        span.in_scope(|| tracing::debug!(status, duration_ms, "request finished"));
+    } else {
+        span.in_scope(|| tracing::info!(status, duration_ms, "request finished"));
+    }
+    Ok(response)
+}
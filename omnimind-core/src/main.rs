@@ -1,225 +1,257 @@
 // src/main.rs for omnimind-core
-use std::io::{Write, BufReader, BufRead};
-use std::fs;
-use std::path::Path;
-use std::fs::File;
-use chrono;
-use std::process::{Command as OsCommand, Stdio, Child, ChildStdin, ChildStdout}; // For spawning Python
-use std::sync::Mutex; // For thread-safe access to Python process handles
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 // Actix Web and Serde imports
-use actix_web::{web, App, HttpServer, Responder, HttpResponse}; // Removed ActixError as it was unused
-use actix_cors::Cors;
+use actix_web::{web, App, HttpRequest, HttpServer, Responder};
+use clap::Parser;
 use serde::{Serialize, Deserialize};
-use serde_json;
+use tracing::Instrument;
+
+mod api;
+mod auth;
+mod cli;
+mod client;
+mod commands;
+mod config;
+mod error;
+mod events;
+mod health;
+mod history;
+mod limits;
+mod logging;
+mod metrics;
+mod nlu;
+mod openapi;
+mod output;
+mod planner;
+mod progress;
+mod registry;
+mod reload;
+mod repl;
+mod response;
+mod script;
+mod server;
+mod siwe;
+mod ws;
+use commands::{add_file_to_ipfs_for_api, cat_file_from_ipfs_for_api, create_note_for_api, get_ipfs_id_for_api, list_directory_contents_for_api};
+use auth::Principal;
+use error::{CommandResult, OmniError};
+use progress::Progress;
+use response::{CommandResponse, CommandStatus};
+use nlu::{NluBackend, NluResponse};
+use nlu::corrections::{CorrectionSource, CorrectionStore};
 
 // --- Structs and Enums ---
 
-// NluResponse: Expected JSON structure from Python NLU script
-#[derive(Debug, Serialize, Deserialize)]
-struct NluResponse {
-    original_text: String,
-    intent: String,
-    predicted_label: String,
-    confidence: f64,
-    arguments_text: String,
-}
-
 // CommandRequest: Expected JSON from client to our API
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, utoipa::ToSchema)]
 struct CommandRequest {
+    /// A command line ("create_note todo"; see the *Arguments schemas) or a natural-language request.
+    #[schema(example = "create_note Shopping list")]
     raw_command: String,
 }
 
-// CommandResponse: Unified JSON structure from our API to client
-#[derive(Debug, Serialize)]
-struct CommandResponse {
-    status: String,
-    message: String,
-    data: Option<serde_json::Value>,
+// PendingClarification: A low-confidence utterance waiting for the user's answer
+struct PendingClarification {
+    utterance: String,
+    nlu_result: NluResponse,
 }
 
-// AppState: Shared state for Actix handlers (Python process handles)
-struct AppState {
-    py_stdin: Mutex<Option<ChildStdin>>,
-    py_stdout_reader: Mutex<Option<BufReader<ChildStdout>>>,
-    #[allow(dead_code)] // py_child_process is kept to keep the process alive
-    py_child_process: Mutex<Option<Child>>, 
+// Session: One client conversation. Clarification questions are answered in the
+// session they were asked in; each caller's plain /command requests share one
+// session (see AppState::http_session).
+struct Session {
+    id: String,
+    pending_clarification: Mutex<Option<PendingClarification>>,
 }
 
-// --- Helper: Consistent Help Message ---
-const HELP_MESSAGE: &str = "Available commands:\n  echo <text>          - Prints back the text you provide.\n  ls [path]            - Lists files and directories.\n  create_note <title>  - Creates a new text note in the 'omni_notes' directory.\n  ipfs_id              - Fetches the ID of the local IPFS node.\n  ipfs_add <file_path> - Adds a local file to IPFS and returns its CID.\n  ipfs_cat <cid>       - Retrieves and displays content from IPFS for a given CID.\n  help                 - Shows this help message.";
+impl Session {
+    fn new(id: impl Into<String>) -> Self {
+        Session { id: id.into(), pending_clarification: Mutex::new(None) }
+    }
+}
 
+// AppState: Shared state for Actix handlers (NLU backend, None in keyword-only mode)
+struct AppState {
+    nlu: RwLock<Option<Arc<dyn NluBackend>>>, // Replaced when a config reload changes NLU settings
+    corrections: CorrectionStore,
+    tokens: auth::TokenStore, // API tokens; empty means authentication is off
+    siwe: siwe::SiweState, // Sign-In with Ethereum nonces and wallet sessions
+    http_sessions: Mutex<HashMap<String, Arc<Session>>>, // POST /command sessions by caller (auth::caller_key)
+}
 
-// --- OS Command Implementations (return CommandResponse) ---
+// Sessions of callers kept at most; idle ones without a pending question are dropped beyond this.
+const MAX_HTTP_SESSIONS: usize = 10_000;
 
-fn list_directory_contents_for_api(dir_path_str: &str) -> CommandResponse {
-    let path = Path::new(dir_path_str);
-    if !path.exists() {
-        return CommandResponse { status: "error".to_string(), message: format!("Path '{}' does not exist.", dir_path_str), data: None };
+impl AppState {
+    // The backend in effect now; requests keep the one they started with across a reload.
+    fn nlu(&self) -> Option<Arc<dyn NluBackend>> {
+        self.nlu.read().ok().and_then(|nlu| nlu.clone())
     }
-    if !path.is_dir() {
-        return CommandResponse { status: "error".to_string(), message: format!("Path '{}' is not a directory.", dir_path_str), data: None };
+
+    // The session a caller's POST /command requests (and its /ws?session=shared
+    // connections) run in, so one caller's "yes" never answers another's question.
+    fn http_session(&self, caller: &str) -> Arc<Session> {
+        let Ok(mut sessions) = self.http_sessions.lock() else {
+            return Arc::new(Session::new(format!("http:{}", caller)));
+        };
+        if sessions.len() >= MAX_HTTP_SESSIONS {
+            sessions.retain(|_, session| Arc::strong_count(session) > 1 || session.pending_clarification.lock().map(|pending| pending.is_some()).unwrap_or(false));
+        }
+        sessions.entry(caller.to_string()).or_insert_with(|| Arc::new(Session::new(format!("http:{}", caller)))).clone()
     }
 
-    let mut entries_vec = Vec::new();
-    match fs::read_dir(path) {
-        Ok(entries) => {
-            for entry_result in entries {
-                if let Ok(entry) = entry_result {
-                    let entry_path = entry.path();
-                    let file_name_str = entry.file_name().to_string_lossy().into_owned();
-                    let entry_type = if entry_path.is_dir() { "Directory" } else if entry_path.is_file() { "File" } else { "Other" };
-                    entries_vec.push(serde_json::json!({ "name": file_name_str, "type": entry_type }));
-                }
-            }
-            CommandResponse {
-                status: "success".to_string(),
-                message: format!("Contents of '{}':", dir_path_str),
-                data: Some(serde_json::Value::Array(entries_vec)),
-            }
+    // Installs a new backend and returns the previous one.
+    fn replace_nlu(&self, backend: Option<Arc<dyn NluBackend>>) -> Option<Arc<dyn NluBackend>> {
+        match self.nlu.write() {
+            Ok(mut nlu) => std::mem::replace(&mut *nlu, backend),
+            Err(_) => backend,
         }
-        Err(e) => CommandResponse { status: "error".to_string(), message: format!("Error reading directory '{}': {}", dir_path_str, e), data: None },
     }
 }
 
-fn create_note_for_api(title: &str) -> CommandResponse {
-    let notes_dir = Path::new("./omni_notes");
-    if !notes_dir.exists() {
-        if let Err(e) = fs::create_dir(notes_dir) {
-            return CommandResponse { status: "error".to_string(), message: format!("Error creating notes directory './omni_notes/': {}", e), data: None };
+
+// --- Built-in Command Implementations (OS and IPFS commands live in commands.rs) ---
+
+fn nlu_cache_for_api(app_state: &AppState, action: Option<&str>) -> CommandResult {
+    let nlu_backend = app_state.nlu();
+    let Some(cache) = nlu_backend.as_ref().and_then(|backend| backend.cache()) else {
+        return Ok(CommandResponse::info("NLU cache is disabled for the current backend."));
+    };
+    match action {
+        None => Ok(CommandResponse::success("NLU cache statistics:", Some(serde_json::json!(cache.stats())))),
+        Some("clear") => {
+            cache.clear();
+            Ok(CommandResponse::success("NLU cache cleared.", Some(serde_json::json!(cache.stats()))))
         }
+        Some(other) => Err(OmniError::InvalidArguments(format!("Unknown nlu_cache action '{}'. Usage: nlu_cache [clear]", other))),
     }
+}
 
-    let sane_title = title.chars().filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_').collect::<String>().replace(" ", "_");
-    if sane_title.is_empty() {
-        return CommandResponse { status: "error".to_string(), message: "Note title invalid after sanitization.".to_string(), data: None };
+// teach "<phrase>" as <command>  (the phrase may also be unquoted)
+fn teach_for_api(app_state: &AppState, rest: &str) -> CommandResult {
+    let usage = "Usage: teach \"<phrase>\" as <command> (e.g. teach \"show my stuff\" as ls omni_notes)";
+    let (phrase, command) = parse_teach_arguments(rest).ok_or_else(|| OmniError::InvalidArguments(usage.to_string()))?;
+    let intent = command.split_whitespace().next().unwrap_or("").to_lowercase();
+    if !registry::is_nlu_intent(&intent) {
+        return Err(OmniError::InvalidArguments(format!("Cannot teach '{}': unknown command. Known commands: {}", intent, registry::nlu_intent_names().join(", "))));
     }
 
-    let file_name = format!("{}.omni.txt", sane_title);
-    let file_path = notes_dir.join(&file_name);
-
-    match File::create(&file_path) {
-        Ok(mut file) => {
-            let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-            let content = format!("OmniMind Note\nTitle: {}\nCreated: {}\n\n---\n(Start your note here)\n", title, timestamp);
-            match file.write_all(content.as_bytes()) {
-                Ok(_) => CommandResponse {
-                    status: "success".to_string(),
-                    message: format!("Successfully created note: '{}'", file_path.display()),
-                    data: Some(serde_json::json!({ "path": file_path.display().to_string() })),
-                },
-                Err(e) => CommandResponse {
-                    status: "success".to_string(), 
-                    message: format!("Created empty note: '{}', but failed to write content: {}", file_path.display(), e),
-                    data: Some(serde_json::json!({ "path": file_path.display().to_string() })),
-                },
-            }
+    let correction = app_state.corrections.record(&phrase, &command, CorrectionSource::Teach)?;
+    Ok(CommandResponse::success(
+        format!("Learned: '{}' now means '{}'.", correction.phrase, correction.command),
+        Some(serde_json::json!(correction)),
+    ))
+}
+
+fn parse_teach_arguments(rest: &str) -> Option<(String, String)> {
+    let (phrase, after) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let closing = rest[1..].find(quote)? + 1;
+            (rest[1..closing].to_string(), rest[closing + 1..].trim_start())
+        }
+        _ => {
+            let split = rest.rfind(" as ")?;
+            (rest[..split].to_string(), &rest[split + 1..])
         }
-        Err(e) => CommandResponse { status: "error".to_string(), message: format!("Error creating note file '{}': {}", file_path.display(), e), data: None },
+    };
+    let command = after.strip_prefix("as ")?.trim();
+    if phrase.trim().is_empty() || command.is_empty() {
+        return None;
     }
+    Some((phrase, command.to_string()))
 }
 
-async fn get_ipfs_id_for_api() -> CommandResponse {
-    let ipfs_api_url = "http://127.0.0.1:5001/api/v0/id";
-    let client = reqwest::Client::new();
-    match client.post(ipfs_api_url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<IpfsIdResponse>().await { // IpfsIdResponse needs to be defined
-                    Ok(ipfs_id_data) => CommandResponse {
-                        status: "success".to_string(),
-                        message: "Successfully fetched IPFS Node ID.".to_string(),
-                        data: Some(serde_json::json!({ "ipfsNodeId": ipfs_id_data.id })),
-                    },
-                    Err(e) => CommandResponse { status: "error".to_string(), message: format!("Failed to parse IPFS ID response: {}", e), data: None },
-                }
+fn corrections_for_api(app_state: &AppState, args_str: &[String]) -> CommandResult {
+    let action = args_str.first().map(|a| a.to_lowercase());
+    match action.as_deref() {
+        None | Some("list") => {
+            let corrections = app_state.corrections.list();
+            Ok(CommandResponse::success(format!("{} taught phrase(s):", corrections.len()), Some(serde_json::json!(corrections))))
+        }
+        Some("export") => match args_str.get(1).map(|f| f.to_lowercase()).as_deref() {
+            None | Some("fewshot") => Ok(CommandResponse::success("Taught phrases as few-shot examples:", Some(app_state.corrections.export_few_shot()))),
+            Some("jsonl") => Ok(CommandResponse::success(
+                "Taught phrases as fine-tuning JSONL (prompt/completion):",
+                Some(serde_json::json!({ "format": "jsonl", "content": app_state.corrections.export_jsonl() })),
+            )),
+            Some(other) => Err(OmniError::InvalidArguments(format!("Unknown export format '{}'. Use fewshot or jsonl.", other))),
+        },
+        Some("forget") if args_str.len() > 1 => {
+            let phrase = args_str[1..].join(" ");
+            let phrase = phrase.trim_matches(|c| c == '"' || c == '\'');
+            if app_state.corrections.forget(phrase)? {
+                Ok(CommandResponse::success(format!("Forgot '{}'.", phrase), None))
             } else {
-                let status_code = response.status();
-                let error_text = response.text().await.unwrap_or_else(|e| format!("Unknown error and failed to get error text: {}", e));
-                CommandResponse { status: "error".to_string(), message: format!("IPFS API request failed with status {}: {}", status_code, error_text), data: None }
+                Err(OmniError::NotFound(format!("No taught phrase matches '{}'.", phrase)))
             }
         }
-        Err(e) => CommandResponse { status: "error".to_string(), message: format!("Failed to connect to IPFS API: {}. Ensure IPFS daemon is running and API server is enabled at {}.", e, ipfs_api_url), data: None },
+        _ => Err(OmniError::InvalidArguments("Usage: corrections [list | export fewshot|jsonl | forget \"<phrase>\"]".to_string())),
     }
 }
-// We need IpfsIdResponse struct from previous step
-#[derive(Debug, Serialize, Deserialize)]
-struct IpfsIdResponse {
-    #[serde(alias = "ID")]
-    id: String,
+
+async fn build_plan(request: &str, app_state: &AppState) -> Result<planner::Plan, planner::PlanError> {
+    let nlu_backend = app_state.nlu();
+    planner::build_plan(request, &app_state.corrections, nlu_backend.as_deref(), config::current().nlu.confidence_threshold).await
 }
 
+// plan <request>: dry run, shows what would be executed.
+async fn preview_plan_for_api(request: &str, app_state: &AppState) -> CommandResult {
+    let plan = build_plan(request, app_state).await?;
+    let preview: Vec<String> = plan.steps.iter().map(|step| format!("{}. {}", step.step, step.preview())).collect();
+    Ok(CommandResponse::success(
+        format!("Plan for '{}' ({} step(s), not executed):\n{}", plan.request, plan.steps.len(), preview.join("\n")),
+        Some(serde_json::json!(plan)),
+    ))
+}
 
-async fn add_file_to_ipfs_for_api(local_file_path_str: &str) -> CommandResponse {
-    let file_path = Path::new(local_file_path_str);
-    if !file_path.exists() { /* ... error handling ... */ return CommandResponse { status: "error".to_string(), message: format!("Local file '{}' does not exist.", local_file_path_str), data: None }; }
-    if !file_path.is_file() { /* ... error handling ... */ return CommandResponse { status: "error".to_string(), message: format!("Path '{}' is not a file.", local_file_path_str), data: None };}
-
-    let file_content_bytes = match fs::read(file_path) { Ok(bytes) => bytes, Err(e) => { return CommandResponse { status: "error".to_string(), message: format!("Failed to read local file '{}': {}", local_file_path_str, e), data: None }; } };
-    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
-    let part = match reqwest::multipart::Part::bytes(file_content_bytes).file_name(file_name.clone()).mime_str("application/octet-stream") { Ok(p) => p, Err(e) => { return CommandResponse { status: "error".to_string(), message: format!("Failed to create multipart part: {}", e), data: None }; }};
-    let form = reqwest::multipart::Form::new().part("file", part);
-    let ipfs_api_url = "http://127.0.0.1:5001/api/v0/add";
-    let client = reqwest::Client::new();
-
-    match client.post(ipfs_api_url).multipart(form).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<IpfsAddResponse>().await { // IpfsAddResponse needs to be defined
-                    Ok(add_data) => CommandResponse {
-                        status: "success".to_string(),
-                        message: format!("File '{}' successfully added to IPFS.", add_data.name),
-                        data: Some(serde_json::json!({ "fileName": add_data.name, "cid": add_data.hash, "size": add_data.size })),
-                    },
-                    Err(e) => CommandResponse { status: "error".to_string(), message: format!("Successfully uploaded but failed to parse IPFS add response: {}", e), data: None },
-                }
-            } else { /* ... error handling for non-success status ... */ 
-                let status_code = response.status();
-                let error_text = response.text().await.unwrap_or_else(|e| format!("Unknown error: {}", e));
-                CommandResponse { status: "error".to_string(), message: format!("IPFS API /add failed with {}: {}", status_code, error_text), data: None }
+// Runs plan steps in order, feeding outputs forward; stops at the first failure.
+async fn execute_plan(plan: &planner::Plan, principal: &Principal, progress: &Progress) -> CommandResult {
+    let mut outputs: Vec<Option<serde_json::Value>> = Vec::new();
+    let mut step_results = Vec::new();
+    let mut failed_step = None; // (step number, its error code)
+
+    for step in &plan.steps {
+        let mut command_line = step.preview();
+        let result = match step.arguments_with(&outputs) {
+            Ok(arguments_text) => {
+                command_line = format!("{} {}", step.command, arguments_text).trim_end().to_string();
+                tracing::info!(step = step.step, "Plan step: {}", command_line);
+                let nlu_result = NluResponse {
+                    original_text: step.text.clone(),
+                    intent: step.command.clone(),
+                    predicted_label: step.resolved_by.clone(),
+                    confidence: step.confidence,
+                    arguments_text,
+                };
+                progress.stage("plan", format!("Step {} of {}: {}", step.step, plan.steps.len(), command_line));
+                execute_nlu_intent(&nlu_result, principal, progress).await
             }
+            Err(e) => Err(OmniError::from(e)),
+        };
+        let response = error::into_response(result);
+        progress.output(format!("Step {}: {}", step.step, response.message), response.data.clone());
+
+        step_results.push(serde_json::json!({
+            "step": step.step,
+            "command": command_line,
+            "status": response.status,
+            "message": response.message,
+            "data": response.data,
+        }));
+        if response.status == CommandStatus::Error {
+            failed_step = Some((step.step, response.code));
+            break;
         }
-        Err(e) => CommandResponse { status: "error".to_string(), message: format!("Failed to connect to IPFS API /add: {}", e), data: None },
+        outputs.push(response.data);
     }
-}
-// We need IpfsAddResponse struct from previous step
-#[derive(Debug, Serialize, Deserialize)]
-struct IpfsAddResponse {
-    #[serde(alias = "Name")]
-    name: String,
-    #[serde(alias = "Hash")]
-    hash: String,
-    #[serde(alias = "Size")]
-    size: String,
-}
 
-async fn cat_file_from_ipfs_for_api(cid_str: &str) -> CommandResponse {
-    if (!cid_str.starts_with("Qm") && !cid_str.starts_with("ba")) || cid_str.len() < 46 { /* ... error handling ... */ return CommandResponse { status: "error".to_string(), message: format!("Invalid CID format: '{}'", cid_str), data: None }; }
-    let ipfs_api_url = format!("http://127.0.0.1:5001/api/v0/cat?arg={}", cid_str);
-    let client = reqwest::Client::new();
-    match client.post(&ipfs_api_url).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.text().await {
-                    Ok(content) => CommandResponse {
-                        status: "success".to_string(),
-                        message: format!("Successfully retrieved content for CID: {}", cid_str),
-                        data: Some(serde_json::json!({ "cid": cid_str, "content": content })),
-                    },
-                    Err(e) => CommandResponse { status: "error".to_string(), message: format!("Failed to read content for CID {}: {}", cid_str, e), data: None },
-                }
-            } else { /* ... detailed error handling for non-success status ... */ 
-                let status_code = response.status();
-                let error_body = response.text().await.unwrap_or_else(|e| format!("Failed to read error body: {}", e));
-                let mut error_message_from_ipfs = error_body.clone();
-                if let Ok(json_error) = serde_json::from_str::<serde_json::Value>(&error_body) {
-                    if let Some(msg) = json_error.get("Message").and_then(|v| v.as_str()) { error_message_from_ipfs = msg.to_string(); }
-                }
-                CommandResponse { status: "error".to_string(), message: format!("IPFS API /cat for {} failed with {}: {}", cid_str, status_code, error_message_from_ipfs), data: None }
-            }
-        }
-        Err(e) => CommandResponse { status: "error".to_string(), message: format!("Failed to connect to IPFS API /cat (CID: {}): {}", cid_str, e), data: None },
+    let details = serde_json::json!({ "request": plan.request, "steps": step_results });
+    match failed_step {
+        None => Ok(CommandResponse::success(format!("Plan completed: {} step(s) succeeded.", plan.steps.len()), Some(details))),
+        Some((step, code)) => Err(OmniError::PlanStepFailed { step, total: plan.steps.len(), code, details }),
     }
 }
 
@@ -228,246 +260,470 @@ async fn cat_file_from_ipfs_for_api(cid_str: &str) -> CommandResponse {
 async fn process_omni_command(
     raw_command_str: &str,
     app_state: web::Data<AppState>, // Pass AppState here
-) -> CommandResponse {
-    let parts: Vec<&str> = raw_command_str.trim().split_whitespace().collect();
+    session: &Session,
+    principal: &Principal,
+    progress: &Progress,
+    resolution: &mut history::Resolution, // Filled in with what the command resolved to
+) -> CommandResult {
+    let expanded_alias = registry::expand_alias(raw_command_str);
+    let raw_command_str = expanded_alias.as_deref().unwrap_or(raw_command_str);
+    let parts: Vec<&str> = raw_command_str.split_whitespace().collect();
     if parts.is_empty() {
-        return CommandResponse { status: "error".to_string(), message: "Empty command.".to_string(), data: None };
+        return Err(OmniError::EmptyCommand);
     }
 
     let command_keyword = parts[0].to_lowercase();
     let args_str: Vec<String> = parts.get(1..).unwrap_or(&[]).iter().map(|s| s.to_string()).collect();
 
+    // A pending "did you mean" question only applies to the command right after it.
+    let pending = session.pending_clarification.lock().ok().and_then(|mut pending| pending.take());
+    if let Some(pending) = pending {
+        if let Some(result) = resolve_clarification(pending, &command_keyword, &args_str, &app_state, principal, progress, resolution).await {
+            return result;
+        }
+    }
+
+    if let Some(spec) = registry::find(&command_keyword) {
+        resolution.command(spec.name, &args_str, if expanded_alias.is_some() { "alias" } else { "command" });
+        principal.require(spec.scope)?;
+    }
+
     // --- Hybrid Approach: Try keyword match first ---
+    let usage_error = |usage: &str| Err(OmniError::InvalidArguments(format!("Usage: {}", usage)));
     let direct_match_result = match command_keyword.as_str() {
-        "echo" => Some(CommandResponse { 
-            status: "success".to_string(), 
-            message: args_str.join(" "), 
-            data: None 
-        }),
-        "help" => Some(CommandResponse { 
-            status: "success".to_string(), 
-            message: HELP_MESSAGE.to_string(), 
-            data: None 
-        }),
-        "ls" => Some(list_directory_contents_for_api(args_str.get(0).map(String::as_str).unwrap_or("."))),
+        "echo" => Some(Ok(CommandResponse::success(args_str.join(" "), None))),
+        "help" => Some(Ok(CommandResponse::success(registry::help_message(), None))),
+        "ls" => Some(list_directory_contents_for_api(args_str.first().map(String::as_str).unwrap_or("."))),
         "create_note" => {
-            if args_str.is_empty() { Some(CommandResponse { status: "error".to_string(), message: "Usage: create_note <title_of_note>".to_string(), data: None }) } 
-            else { Some(create_note_for_api(&args_str.join(" "))) }
+            if args_str.is_empty() { Some(usage_error("create_note <title_of_note>")) }
+            else { Some(create_note_for_api(&args_str.join(" "), None, true)) }
         },
         "ipfs_id" => Some(get_ipfs_id_for_api().await),
         "ipfs_add" => {
-            if args_str.is_empty() { Some(CommandResponse { status: "error".to_string(), message: "Usage: ipfs_add <local_file_path>".to_string(), data: None }) }
-            else { Some(add_file_to_ipfs_for_api(&args_str.join(" ")).await) }
+            if args_str.is_empty() { Some(usage_error("ipfs_add <local_file_path>")) }
+            else { Some(add_file_to_ipfs_for_api(&args_str.join(" "), progress).await) }
         },
         "ipfs_cat" => {
-            if args_str.is_empty() { Some(CommandResponse { status: "error".to_string(), message: "Usage: ipfs_cat <ipfs_cid>".to_string(), data: None }) }
-            else { Some(cat_file_from_ipfs_for_api(&args_str[0]).await) }
+            if args_str.is_empty() { Some(usage_error("ipfs_cat <ipfs_cid>")) }
+            else { Some(cat_file_from_ipfs_for_api(&args_str[0], progress).await) }
+        },
+        "plan" => {
+            let request = raw_command_str.trim()[parts[0].len()..].trim();
+            if request.is_empty() { Some(usage_error("plan <request>")) }
+            else { Some(preview_plan_for_api(request, &app_state).await) }
         },
+        "teach" => Some(teach_for_api(&app_state, raw_command_str.trim()[parts[0].len()..].trim())),
+        "corrections" => Some(corrections_for_api(&app_state, &args_str)),
+        "nlu_cache" => Some(nlu_cache_for_api(&app_state, args_str.first().map(String::as_str))),
+        "history" => Some(history::history_for_api(&args_str).await),
+        "verify_audit" => Some(history::verify_audit_for_api().await),
         _ => None, // No direct keyword match
     };
 
-    if let Some(response) = direct_match_result {
-        return response; // Return if keyword matched
+    if let Some(result) = direct_match_result {
+        return result; // Return if keyword matched
     }
 
-    // --- If no direct keyword match, try NLU fallback ---
-    println!("No direct command match for '{}'. Trying NLU fallback...", raw_command_str.trim());
-    
-    let py_stdin_opt = app_state.py_stdin.lock();
-    let py_stdout_reader_opt = app_state.py_stdout_reader.lock();
-
-    let mut py_stdin_guard = match py_stdin_opt { Ok(g) => g, Err(_) => return CommandResponse { status: "error".to_string(), message: "NLU stdin lock error".to_string(), data: None }};
-    let mut py_stdout_reader_guard = match py_stdout_reader_opt { Ok(g) => g, Err(_) => return CommandResponse { status: "error".to_string(), message: "NLU stdout lock error".to_string(), data: None }};
-
-    let py_stdin = match py_stdin_guard.as_mut() { Some(s) => s, None => return CommandResponse { status: "error".to_string(), message: "NLU stdin not available.".to_string(), data: None }};
-    let py_stdout_reader = match py_stdout_reader_guard.as_mut() { Some(r) => r, None => return CommandResponse { status: "error".to_string(), message: "NLU stdout not available.".to_string(), data: None }};
-    
-    if writeln!(py_stdin, "{}", raw_command_str.trim()).is_err() || py_stdin.flush().is_err() {
-        eprintln!("Failed to write/flush to Python NLU stdin.");
-        return CommandResponse { status: "error".to_string(), message: "Failed to send command to NLU service.".to_string(), data: None };
+    // --- If no direct keyword match, try taught phrases, then NLU fallback ---
+    tracing::debug!("No direct command match for '{}'. Trying NLU fallback...", raw_command_str.trim());
+
+    let nlu_result = match app_state.corrections.lookup(raw_command_str.trim()) {
+        Some(taught) => taught,
+        None if planner::split_request(raw_command_str).len() > 1 => {
+            progress.stage("plan", "Planning a multi-step request...");
+            let plan = build_plan(raw_command_str, &app_state).await?;
+            let steps: Vec<&str> = plan.steps.iter().map(|step| step.command.as_str()).collect();
+            resolution.command(&steps.join(" + "), &[], "plan");
+            return execute_plan(&plan, principal, progress).await;
+        }
+        None => {
+            let nlu_backend = app_state.nlu().ok_or_else(|| {
+                OmniError::UnknownCommand(format!("Unknown command '{}'. NLU is disabled (keyword-only mode); type 'help' for available commands.", command_keyword))
+            })?;
+            progress.stage("nlu", format!("Classifying with the {} NLU backend...", nlu_backend.name()));
+            nlu_backend.classify(raw_command_str.trim()).await?
+        }
+    };
+
+    resolution.nlu(&nlu_result);
+    tracing::info!(intent = %nlu_result.intent, confidence = nlu_result.confidence, arguments = %nlu_result.arguments_text, "NLU result");
+    if nlu_result.confidence >= config::current().nlu.confidence_threshold {
+        return execute_nlu_intent(&nlu_result, principal, progress).await;
     }
 
-    let mut nlu_response_json_str = String::new();
-    if py_stdout_reader.read_line(&mut nlu_response_json_str).is_err() {
-        eprintln!("Failed to read from Python NLU stdout.");
-        return CommandResponse { status: "error".to_string(), message: "Failed to receive response from NLU service.".to_string(), data: None };
+    // Too unsure to act: ask the user, and remember the question for their next command.
+    if !registry::is_nlu_intent(&nlu_result.intent) {
+        return Err(OmniError::LowConfidence {
+            message: format!("NLU confidence {:.2} for intent '{}' too low.", nlu_result.confidence, nlu_result.intent),
+            details: serde_json::json!({"nlu_result": nlu_result}),
+        });
+    }
+    let remembered = if principal.allows("nlu:admin") { " (OmniMind will remember this phrase)" } else { "" };
+    let message = format!(
+        "NLU confidence {:.2} for intent '{}' too low. Did you mean '{}'? Reply 'yes' to run it{}, 'no' to skip, or the command name you meant.",
+        nlu_result.confidence, nlu_result.intent, nlu_result.intent, remembered
+    );
+    let details = serde_json::json!({"nlu_result": nlu_result, "clarification": {"utterance": raw_command_str.trim(), "suggested_intent": nlu_result.intent}});
+    if let Ok(mut pending) = session.pending_clarification.lock() {
+        *pending = Some(PendingClarification { utterance: raw_command_str.trim().to_string(), nlu_result });
     }
+    Err(OmniError::LowConfidence { message, details })
+}
 
-    match serde_json::from_str::<NluResponse>(nlu_response_json_str.trim()) {
-        Ok(nlu_result) => {
-            println!("NLU Result: intent='{}', confidence={:.2}, args='{}'", 
-                     nlu_result.intent, nlu_result.confidence, nlu_result.arguments_text);
-            const NLU_CONFIDENCE_THRESHOLD: f64 = 0.5;
-            if nlu_result.confidence >= NLU_CONFIDENCE_THRESHOLD {
-                let nlu_command_keyword = nlu_result.intent.as_str();
-                let nlu_args_str: Vec<String> = nlu_result.arguments_text.split_whitespace().map(String::from).collect();
-                
-                            // Re-dispatch based on NLU result
-                            // ... inside if nlu_result.confidence >= NLU_CONFIDENCE_THRESHOLD {
-                let mut command_execution_response = match nlu_command_keyword {
-                    "echo" => CommandResponse { 
-                        status: "success".to_string(), 
-                        message: nlu_args_str.join(" "), 
-                        data: None // Base data
-                    },
-                    "help" => {
-                        CommandResponse { 
-                            status: "success".to_string(), 
-                            message: HELP_MESSAGE.to_string(), 
-                            data: None // Base data
-                        }
-                    },
-                    "ls" => {
-                        // Recalculate path_to_list for 'ls' as done before
-                        let mut path_to_list_nlu = ".".to_string();
-                        let args_text_lower_nlu = nlu_result.arguments_text.to_lowercase();
-                        if args_text_lower_nlu.contains("parent directory") { path_to_list_nlu = "..".to_string(); }
-                        // ... (add other path logic from previous ls NLU handler) ...
-                        else if !nlu_args_str.is_empty() {
-                            let first_arg_lower = nlu_args_str[0].to_lowercase();
-                            if !["me", "my", "the", "a", "in", "all"].contains(&first_arg_lower.as_str()) {
-                                path_to_list_nlu = nlu_args_str[0].clone();
-                            }
-                        }
-                        println!("NLU for 'ls': determined path_to_list = '{}' from args_text = '{}'", 
-                                path_to_list_nlu, nlu_result.arguments_text);
-                        list_directory_contents_for_api(&path_to_list_nlu)
-                    },
-                    "create_note" => {
-                        if nlu_args_str.is_empty() { CommandResponse { status: "error".to_string(), message: "NLU: create_note requires a title.".to_string(), data: None } }
-                        else { create_note_for_api(&nlu_args_str.join(" ")) }
-                    },
-                    "ipfs_id" => get_ipfs_id_for_api().await,
-                    "ipfs_add" => {
-                        if nlu_args_str.is_empty() { CommandResponse { status: "error".to_string(), message: "NLU: ipfs_add requires a file path.".to_string(), data: None } }
-                        else { add_file_to_ipfs_for_api(&nlu_args_str.join(" ")).await }
-                    },
-                    "ipfs_cat" => {
-                        if nlu_args_str.is_empty() { CommandResponse { status: "error".to_string(), message: "NLU: ipfs_cat requires a CID.".to_string(), data: None } }
-                        else { cat_file_from_ipfs_for_api(&nlu_args_str[0]).await }
-                    },
-                    "quit" => CommandResponse {
-                        status: "info".to_string(),
-                        message: "NLU suggested 'quit'. Server does not quit via API. Use Ctrl+C on server.".to_string(),
-                        data: None,
-                    },
-                    _ => CommandResponse { 
-                        status: "error".to_string(), 
-                        message: format!("NLU identified intent '{}', but it's unhandled after NLU processing.", nlu_result.intent),
-                        data: None // Base data
-                    }
-                };
+// Handles the answer to a "did you mean" question. Returns None when the command
+// is not an answer, in which case it is processed normally.
+async fn resolve_clarification(
+    pending: PendingClarification,
+    command_keyword: &str,
+    args_str: &[String],
+    app_state: &AppState,
+    principal: &Principal,
+    progress: &Progress,
+    resolution: &mut history::Resolution,
+) -> Option<CommandResult> {
+    let chosen_intent = match command_keyword {
+        "yes" | "y" if args_str.is_empty() => pending.nlu_result.intent.clone(),
+        "no" | "n" if args_str.is_empty() => {
+            return Some(Ok(CommandResponse::info(format!("OK, not running anything for '{}'.", pending.utterance))));
+        }
+        keyword if args_str.is_empty() && registry::is_nlu_intent(keyword) => keyword.to_string(),
+        _ => return None,
+    };
 
-                // Now, augment the response with NLU confidence if it was a success/info
-                if command_execution_response.status == "success" || command_execution_response.status == "info" {
-                    let nlu_info = serde_json::json!({
-                        "nlu_confidence": nlu_result.confidence,
-                        "nlu_predicted_label": nlu_result.predicted_label,
-                        "nlu_intent_mapped_to": nlu_result.intent
-                    });
-
-                    if let Some(existing_data) = command_execution_response.data.take() {
-                        // If there was already data, merge NLU info into it.
-                        // This assumes existing_data is an object, or we create a new object.
-                        if let serde_json::Value::Object(mut map) = existing_data {
-                            map.insert("nlu_details".to_string(), nlu_info);
-                            command_execution_response.data = Some(serde_json::Value::Object(map));
-                        } else {
-                            // If existing_data wasn't an object, or we want to keep it separate
-                            command_execution_response.data = Some(serde_json::json!({
-                                "original_data": existing_data,
-                                "nlu_details": nlu_info
-                            }));
-                        }
-                    } else {
-                        command_execution_response.data = Some(nlu_info);
-                    }
+    // Corrections apply to everyone's utterances, so recording one needs the scope `teach` needs.
+    if principal.allows("nlu:admin") {
+        let command = format!("{} {}", chosen_intent, pending.nlu_result.arguments_text);
+        if let Err(e) = app_state.corrections.record(&pending.utterance, &command, CorrectionSource::Clarification) {
+            tracing::warn!("Failed to record clarification for '{}': {}", pending.utterance, e);
+        }
+    }
+    let nlu_result = NluResponse {
+        intent: chosen_intent,
+        predicted_label: format!("clarified: {}", pending.utterance),
+        confidence: 1.0,
+        ..pending.nlu_result
+    };
+    resolution.nlu(&nlu_result);
+    Some(execute_nlu_intent(&nlu_result, principal, progress).await)
+}
+
+// Runs the command an NLU result (or taught phrase) resolved to, and tags the
+// response with how it was understood.
+async fn execute_nlu_intent(nlu_result: &NluResponse, principal: &Principal, progress: &Progress) -> CommandResult {
+    let nlu_command_keyword = nlu_result.intent.as_str();
+    if let Some(spec) = registry::find(nlu_command_keyword) {
+        principal.require(spec.scope)?;
+    }
+    let nlu_args_str: Vec<String> = nlu_result.arguments_text.split_whitespace().map(String::from).collect();
+    let missing_argument = |what: &str| Err(OmniError::InvalidArguments(format!("NLU: {} requires {}.", nlu_command_keyword, what)));
+
+    // Re-dispatch based on NLU result
+    let mut command_execution_response = match nlu_command_keyword {
+        "echo" => CommandResponse::success(nlu_args_str.join(" "), None),
+        "help" => CommandResponse::success(registry::help_message(), None),
+        "ls" => {
+            // Pick the directory to list from the NLU arguments
+            let mut path_to_list_nlu = ".".to_string();
+            let args_text_lower_nlu = nlu_result.arguments_text.to_lowercase();
+            if args_text_lower_nlu.contains("parent directory") { path_to_list_nlu = "..".to_string(); }
+            else if !nlu_args_str.is_empty() {
+                let first_arg_lower = nlu_args_str[0].to_lowercase();
+                if !["me", "my", "the", "a", "in", "all"].contains(&first_arg_lower.as_str()) {
+                    path_to_list_nlu = nlu_args_str[0].clone();
                 }
-                return command_execution_response; // Return the augmented response
-            } else {
-                CommandResponse { status: "error".to_string(), message: format!("NLU confidence {:.2} for intent '{}' too low.", nlu_result.confidence, nlu_result.intent), data: Some(serde_json::json!({"nlu_result": nlu_result})) }
             }
+            tracing::debug!("NLU for 'ls': determined path_to_list = '{}' from args_text = '{}'", path_to_list_nlu, nlu_result.arguments_text);
+            list_directory_contents_for_api(&path_to_list_nlu)?
+        },
+        "create_note" => {
+            if nlu_args_str.is_empty() { return missing_argument("a title"); }
+            create_note_for_api(&nlu_args_str.join(" "), None, true)?
+        },
+        "ipfs_id" => get_ipfs_id_for_api().await?,
+        "ipfs_add" => {
+            if nlu_args_str.is_empty() { return missing_argument("a file path"); }
+            add_file_to_ipfs_for_api(&nlu_args_str.join(" "), progress).await?
+        },
+        "ipfs_cat" => {
+            if nlu_args_str.is_empty() { return missing_argument("a CID"); }
+            cat_file_from_ipfs_for_api(&nlu_args_str[0], progress).await?
+        },
+        "quit" => CommandResponse::info("NLU suggested 'quit'. Server does not quit via API. Use Ctrl+C on server."),
+        _ => return Err(OmniError::UnknownCommand(format!("NLU identified intent '{}', but it's unhandled after NLU processing.", nlu_result.intent))),
+    };
+
+    // Now, augment the successful response with how the NLU understood it
+    let nlu_info = serde_json::json!({
+        "nlu_confidence": nlu_result.confidence,
+        "nlu_predicted_label": nlu_result.predicted_label,
+        "nlu_intent_mapped_to": nlu_result.intent
+    });
+
+    if let Some(existing_data) = command_execution_response.data.take() {
+        // If there was already data, merge NLU info into it.
+        // This assumes existing_data is an object, or we create a new object.
+        if let serde_json::Value::Object(mut map) = existing_data {
+            map.insert("nlu_details".to_string(), nlu_info);
+            command_execution_response.data = Some(serde_json::Value::Object(map));
+        } else {
+            // If existing_data wasn't an object, or we want to keep it separate
+            command_execution_response.data = Some(serde_json::json!({
+                "original_data": existing_data,
+                "nlu_details": nlu_info
+            }));
         }
-        Err(e) => {
-            eprintln!("Failed to parse NLU JSON response: {}. Raw: '{}'", e, nlu_response_json_str.trim());
-            CommandResponse { status: "error".to_string(), message: "Error processing NLU response.".to_string(), data: None }
-        }
+    } else {
+        command_execution_response.data = Some(nlu_info);
     }
+    Ok(command_execution_response) // Return the augmented response
 }
 
 // --- Actix Web Handler ---
+#[utoipa::path(
+    post, path = "/command", tag = "commands",
+    request_body = CommandRequest,
+    responses(
+        (status = 200, description = "The command succeeded (status success or info)", body = CommandResponse),
+        (status = 400, description = "Empty or unknown command, or invalid arguments", body = CommandResponse),
+        (status = 401, description = "Missing or invalid API token", body = CommandResponse),
+        (status = 403, description = "The token lacks the scope of the command", body = CommandResponse),
+        (status = 404, description = "A path or taught phrase was not found", body = CommandResponse),
+        (status = 409, description = "verify_audit found the audit log tampered with", body = CommandResponse),
+        (status = 413, description = "raw_command is longer than limits.max_command_length (command_too_long), or ipfs_add's file is larger than limits.max_upload_bytes (upload_too_large)", body = CommandResponse),
+        (status = 422, description = "The request could not be understood confidently", body = CommandResponse),
+        (status = 429, description = "The caller exceeded its rate limit (rate_limited); see Retry-After", body = CommandResponse),
+        (status = 500, description = "Local I/O or internal failure", body = CommandResponse),
+        (status = 502, description = "IPFS or the NLU service failed", body = CommandResponse),
+        (status = 503, description = "The NLU backend is unavailable, or busy with limits.nlu_max_concurrent requests (nlu_busy)", body = CommandResponse),
+        (status = 504, description = "The NLU backend timed out", body = CommandResponse),
+    )
+)]
 async fn handle_command_request(
+    http_req: HttpRequest,
     req: web::Json<CommandRequest>,
     app_state: web::Data<AppState>, // Inject AppState
+    principal: web::ReqData<Principal>, // Set by auth::require_token
 ) -> impl Responder {
-    let response = process_omni_command(&req.raw_command, app_state).await;
-    HttpResponse::Ok().json(response)
+    if let Err(error) = limits::check_command_length(&req.raw_command) {
+        return api::respond(Err(error));
+    }
+    let session = app_state.http_session(&auth::caller_key(&principal, http_req.peer_addr()));
+    let response = execute_command(&req.raw_command, app_state.clone(), &session, &principal, &Progress::none()).await;
+    api::respond(Ok(response))
+}
+
+// Runs one command in-process for the terminal modes (repl.rs, script.rs),
+// passing progress reports (stages, plan steps) to `on_progress` as they arrive.
+async fn run_local_command(line: &str, app_state: &web::Data<AppState>, session: &Session, on_progress: impl Fn(&progress::ProgressEvent)) -> CommandResponse {
+    let (progress, mut events) = Progress::channel();
+    let principal = Principal::local();
+    let command = execute_command(line, app_state.clone(), session, &principal, &progress);
+    tokio::pin!(command);
+    let response = loop {
+        tokio::select! {
+            response = &mut command => break response,
+            Some(event) = events.recv() => on_progress(&event),
+        }
+    };
+    while let Ok(event) = events.try_recv() {
+        on_progress(&event);
+    }
+    response
+}
+
+// Runs one command for any front end: dispatches it in a `command` span, then
+// publishes command_executed, records its metrics and appends it to the history
+// with what it resolved to. Commands sent over /command keep the request's ID; the others get their own.
+async fn execute_command(raw_command: &str, app_state: web::Data<AppState>, session: &Session, principal: &Principal, progress: &Progress) -> CommandResponse {
+    let request_id = logging::request_id().unwrap_or_else(logging::new_request_id);
+    let span = tracing::info_span!("command", request_id = %request_id, session = %session.id, caller = %principal.name, raw_command = %raw_command.trim());
+    let command = async {
+        let started = Instant::now();
+        let mut resolution = history::Resolution::default();
+        let response = error::into_response(process_omni_command(raw_command, app_state, session, principal, progress, &mut resolution).await);
+        let duration_ms = started.elapsed().as_millis() as u64;
+        tracing::info!(status = ?response.status, code = response.code.map(tracing::field::debug), duration_ms, "command finished");
+        record_command_outcome(&session.id, principal, raw_command, resolution, &response, started.elapsed());
+        response
+    };
+    logging::with_request_id(request_id, command.instrument(span)).await
+}
+
+// What every command goes through once it has run, whichever front end ran it
+// (the REST endpoints in api.rs included): command_executed for /events
+// subscribers, metrics, and an entry in the history and audit log.
+pub(crate) fn record_command_outcome(session: &str, principal: &Principal, raw_command: &str, resolution: history::Resolution, response: &CommandResponse, duration: Duration) {
+    events::publish(events::CoreEvent::CommandExecuted {
+        session: session.to_string(),
+        raw_command: raw_command.trim().to_string(),
+        status: response.status,
+        code: response.code,
+        message: response.message.clone(),
+    });
+    metrics::record_command(&resolution, response, duration);
+    history::record(session, principal, raw_command, resolution, response, duration);
 }
 
 // --- Main function to start the server ---
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("🚀 OmniMind Core API server starting...");
-
-    let python_executable = "python"; // Or "python3"
-    // IMPORTANT: Adjust this path if omnimind-core is not run from its own directory
-    // For now, assumes `cargo run` is executed from within `omnimind-core/`
-    let nlu_script_path = "../omnimind-nlu-py/nlu_server.py"; 
+    let cli = cli::Cli::parse();
+    let (config, sources) = match config::load(cli.config.config_file.as_deref(), &cli.config.overrides()) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+    // The terminal modes only log warnings unless a level was configured.
+    let terminal = !matches!(cli.command, None | Some(cli::CliCommand::Serve));
+    logging::init(&config.log, (terminal && sources.of("log.level") == "default").then_some("warn"));
+    config::install(config);
+    let exit_code = match cli.command {
+        None | Some(cli::CliCommand::Serve) => return serve(cli.config).await,
+        Some(cli::CliCommand::Repl) => repl::run(client::Target::Local).await,
+        Some(cli::CliCommand::Run { command, output }) => script::run_command(&command.join(" "), output).await,
+        Some(cli::CliCommand::Script { file, vars, keep_going, output }) => script::run_script(&client::Target::Local, &file, &vars, keep_going, output).await,
+        Some(cli::CliCommand::Client { url, token, action }) => client::run(url, token, action).await,
+        Some(cli::CliCommand::Token { action }) => cli::run_token_command(action),
+        Some(cli::CliCommand::Config { action }) => cli::run_config_command(action, &sources),
+    };
+    // The commands a terminal mode ran are still being appended to the history.
+    history::written().await;
+    std::process::exit(exit_code)
+}
 
-    println!("Attempting to spawn Python NLU script: {} {}", python_executable, nlu_script_path);
+// Loads local state and starts the configured NLU backend (nlu.backend: python,
+// http, rules or none). Shared by the server and the terminal modes, which run
+// commands in-process (see repl.rs).
+fn build_app_state(config: &config::Config) -> Result<AppState, OmniError> {
+    let tokens = auth::TokenStore::load(config.storage.tokens_file.clone())?;
+    let nlu_backend = nlu::build_backend(&nlu::NluSettings::from_config(&config.nlu));
+    match &nlu_backend {
+        Some(backend) => tracing::info!("NLU backend: {}", backend.name()),
+        None => tracing::info!("NLU disabled: running in keyword-only mode."),
+    }
+    Ok(AppState {
+        nlu: RwLock::new(nlu_backend.map(Arc::from)),
+        corrections: CorrectionStore::load(config.storage.corrections_file.clone()),
+        tokens,
+        siwe: siwe::SiweState::default(),
+        http_sessions: Mutex::new(HashMap::new()),
+    })
+}
 
-    let mut py_process_cmd = OsCommand::new(python_executable);
-    py_process_cmd.arg(nlu_script_path)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::inherit()); // Inherit stderr to see Python logs/errors directly
+// The route table. Resources registered with auth::scoped need that scope;
+// /command and /ws check the scope of each command they run.
+fn configure_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/command", web::post().to(handle_command_request))
+        .route("/openapi.json", web::get().to(openapi::openapi_json))
+        .route("/ws", web::get().to(ws::ws_session))
+        .service(auth::scoped("/events", "events:read").route(web::get().to(events::events_stream)))
+        .service(auth::scoped("/metrics", "metrics:read").route(web::get().to(metrics::metrics_endpoint)))
+        .route("/healthz", web::get().to(health::healthz))
+        .route("/readyz", web::get().to(health::readyz))
+        .configure(api::configure)
+        .configure(siwe::configure);
+}
 
-    // Set current directory for the Python script if needed, so it can find models if using relative paths.
-    // For Hugging Face cache, this is usually not needed as it uses user's home dir.
-    // py_process_cmd.current_dir("../omnimind-nlu-py/"); // Example if script needs specific CWD
+async fn serve(config_flags: cli::ConfigFlags) -> std::io::Result<()> {
+    tracing::info!("OmniMind Core API server starting...");
+    let config = config::current();
 
-    let mut py_process = match py_process_cmd.spawn() {
-        Ok(child) => {
-            println!("✅ Python NLU script spawned successfully (PID: {}).", child.id());
-            child
+    let tls_config = match server::rustls_config(&config.server) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("{}", e);
+            std::process::exit(1);
         }
+    };
+    let app_state = match build_app_state(&config) {
+        Ok(app_state) => web::Data::new(app_state),
         Err(e) => {
-            eprintln!("❌ Failed to spawn Python NLU script: {}", e);
-            eprintln!("Ensure Python is installed ('{}') and accessible via PATH.", python_executable);
-            eprintln!("Ensure the script exists at relative path: '{}' from where omnimind-core is run.", nlu_script_path);
-            std::process::exit(1); 
+            tracing::error!("{}", e);
+            std::process::exit(1);
         }
     };
+    if app_state.tokens.is_enabled() {
+        tracing::info!("API token authentication enabled ({} token(s)).", app_state.tokens.list().len());
+    } else {
+        tracing::warn!("No API tokens configured: the API is open to any local client. Create one with `omnimind-core token create <name> --scopes ...`.");
+    }
 
-    let py_stdin = py_process.stdin.take().expect("Failed to open Python stdin pipe");
-    let py_stdout = py_process.stdout.take().expect("Failed to open Python stdout pipe");
-    let py_stdout_reader = BufReader::new(py_stdout);
-    
-    let app_state = web::Data::new(AppState {
-        py_stdin: Mutex::new(Some(py_stdin)),
-        py_stdout_reader: Mutex::new(Some(py_stdout_reader)),
-        py_child_process: Mutex::new(Some(py_process)),
-    });
+    tracing::info!("OmniMind Core API server listening on {}", server::listen_urls(&config.server).join(" and "));
+    tracing::info!("CORS origins: {}", config.server.cors_origins.join(", "));
+    tracing::info!("Send POST requests to /command with JSON: {{ \"raw_command\": \"your command here\" }}");
 
-    let server_address = "127.0.0.1:3030";
-    println!("🎧 OmniMind Core API server listening on http://{}", server_address);
-    println!("Send POST requests to /command with JSON: {{ \"raw_command\": \"your command here\" }}");
+    // Publishes note_created / note_changed / note_deleted on /events.
+    actix_web::rt::spawn(events::watch_notes());
+    // Adds the audit log's chain head to IPFS every history.anchor_interval_secs.
+    actix_web::rt::spawn(history::anchor_periodically());
+    // Applies edits to the config file without a restart.
+    actix_web::rt::spawn(reload::watch_config(app_state.clone(), config_flags.config_file.clone(), config_flags.overrides()));
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
-              .allowed_origin("http://localhost:3000") 
-              .allowed_methods(vec!["GET", "POST"])   
-              .allowed_headers(vec![actix_web::http::header::AUTHORIZATION, actix_web::http::header::ACCEPT, actix_web::http::header::CONTENT_TYPE])
-              .max_age(3600);
+    health::mark_started();
+    let shutdown_state = app_state.clone();
+    let mut http_server = HttpServer::new(move || {
+        let cors = server::cors();
 
         App::new()
             .app_data(app_state.clone()) 
-            .wrap(cors)
-            .route("/command", web::post().to(handle_command_request))
-    })
-    .bind(server_address)?
-    .run()
-    .await
-    // Graceful shutdown of Python script would go here if we implement it.
-    // For example, by taking py_child_process from app_state and sending "__EXIT__" or killing it.
-}
\ No newline at end of file
+            .wrap(actix_web::middleware::from_fn(limits::rate_limit)) // Inside auth, to charge the token rather than the address
+            .wrap(actix_web::middleware::from_fn(auth::require_token))
+            .wrap(actix_web::middleware::from_fn(logging::trace_request))
+            .wrap(cors) // Outermost, so preflight requests and 401s get CORS headers
+            .configure(configure_routes)
+    });
+
+    if let Some(address) = server::tcp_address(&config.server) {
+        http_server = match tls_config {
+            Some(config) => http_server.bind_rustls_0_23(address, config)?,
+            None => http_server.bind(address)?,
+        };
+    }
+    #[cfg(unix)]
+    if let Some(path) = &config.server.unix_socket {
+        if let Err(e) = server::remove_stale_socket(path) {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+        http_server = http_server.bind_uds(path)?;
+    }
+    let result = http_server.run().await;
+    // The config watcher still holds AppState, so release the NLU backend
+    // explicitly; this shuts the Python NLU script down (see PythonNluBackend's Drop)
+    // and writes out the NLU cache (NluCache's Drop).
+    drop(shutdown_state.replace_nlu(None));
+    history::written().await;
+    result
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn teach(rest: &str) -> Option<(String, String)> {
+        parse_teach_arguments(rest)
+    }
+
+    #[test]
+    fn quoted_teach_phrases_may_contain_as() {
+        assert_eq!(teach("\"save this as a note\" as create_note inbox"), Some(("save this as a note".to_string(), "create_note inbox".to_string())));
+        assert_eq!(teach("'my stuff' as ls omni_notes"), Some(("my stuff".to_string(), "ls omni_notes".to_string())));
+    }
+
+    #[test]
+    fn unquoted_teach_phrases_split_at_the_last_as() {
+        assert_eq!(teach("show me what I have as list_notes"), Some(("show me what I have".to_string(), "list_notes".to_string())));
+        assert_eq!(teach("known as the list as list_notes"), Some(("known as the list".to_string(), "list_notes".to_string())));
+    }
+
+    #[test]
+    fn teach_needs_a_phrase_and_a_command() {
+        assert_eq!(teach(""), None);
+        assert_eq!(teach("\"unclosed as ls"), None);
+        assert_eq!(teach("\"\" as ls"), None);
+        assert_eq!(teach("\"my stuff\" as   "), None);
+        assert_eq!(teach("\"my stuff\" ls"), None);
+        assert_eq!(teach("my stuff"), None);
+    }
+}
@@ -0,0 +1,167 @@
+// src/metrics.rs for omnimind-core
+// Prometheus metrics, served in the text exposition format on GET /metrics
+// (scope metrics:read). Counters and histograms are recorded where the work
+// happens: execute_command for commands and how they were dispatched,
+// call_with_policy for NLU service latency, send_to_ipfs for IPFS calls, and
+// the NLU backend and WebSocket session lifecycles. Label values come from
+// fixed sets (command names, statuses, error codes), never from user input.
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+use prometheus::{Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+
+use crate::history::Resolution;
+use crate::nlu::NluError;
+use crate::registry;
+use crate::response::CommandResponse;
+
+// Seconds; NLU calls on CPU and large IPFS uploads run into the tens of seconds.
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+const CONFIDENCE_BUCKETS: &[f64] = &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.95, 1.0];
+
+struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,           // {command, status, code}
+    command_duration: HistogramVec,    // {command}
+    dispatch: IntCounterVec,           // {via}: keyword, alias, nlu, plan, rest or none
+    nlu_duration: HistogramVec,        // {backend, outcome}
+    nlu_confidence: Histogram,
+    nlu_restarts: IntCounterVec,       // {backend, reason}
+    ipfs_duration: HistogramVec,       // {endpoint}
+    ipfs_errors: IntCounterVec,        // {endpoint}
+    active_sessions: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let counter = |name: &str, help: &str, labels: &[&str]| {
+            let counter = IntCounterVec::new(Opts::new(name, help), labels).expect("valid counter");
+            registry.register(Box::new(counter.clone())).expect("unique metric name");
+            counter
+        };
+        let histogram = |name: &str, help: &str, labels: &[&str], buckets: &[f64]| {
+            let histogram = HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets.to_vec()), labels).expect("valid histogram");
+            registry.register(Box::new(histogram.clone())).expect("unique metric name");
+            histogram
+        };
+
+        let commands = counter("omnimind_commands_total", "Commands executed, by command name, status and error code.", &["command", "status", "code"]);
+        let command_duration = histogram("omnimind_command_duration_seconds", "Time to run a command, by command name.", &["command"], LATENCY_BUCKETS);
+        let dispatch = counter("omnimind_command_dispatch_total", "How commands were resolved: keyword, alias, nlu (including taught phrases and clarifications), plan, rest or none.", &["via"]);
+        let nlu_duration = histogram("omnimind_nlu_request_duration_seconds", "NLU service calls (python and http backends, retries included), by backend and outcome.", &["backend", "outcome"], LATENCY_BUCKETS);
+        let nlu_confidence = Histogram::with_opts(HistogramOpts::new("omnimind_nlu_confidence", "Confidence of the NLU results commands were dispatched on.").buckets(CONFIDENCE_BUCKETS.to_vec())).expect("valid histogram");
+        registry.register(Box::new(nlu_confidence.clone())).expect("unique metric name");
+        let nlu_restarts = counter("omnimind_nlu_restarts_total", "NLU backend restarts, by backend and reason (exited or reconfigured).", &["backend", "reason"]);
+        let ipfs_duration = histogram("omnimind_ipfs_request_duration_seconds", "IPFS HTTP API calls, by endpoint.", &["endpoint"], LATENCY_BUCKETS);
+        let ipfs_errors = counter("omnimind_ipfs_errors_total", "Failed IPFS HTTP API calls (unreachable or non-2xx), by endpoint.", &["endpoint"]);
+        let active_sessions = IntGauge::new("omnimind_active_sessions", "Open WebSocket sessions.").expect("valid gauge");
+        registry.register(Box::new(active_sessions.clone())).expect("unique metric name");
+
+        Metrics { registry, commands, command_duration, dispatch, nlu_duration, nlu_confidence, nlu_restarts, ipfs_duration, ipfs_errors, active_sessions }
+    }
+}
+
+fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+// --- Recording ---
+
+// One executed command, labelled with what the dispatcher resolved it to.
+pub fn record_command(resolution: &Resolution, response: &CommandResponse, duration: Duration) {
+    let via = match resolution.resolved_by.as_deref() {
+        None => "none",
+        Some("command") => "keyword",
+        Some("alias") => "alias",
+        Some("plan") => "plan",
+        Some("rest") => "rest",
+        Some(_) => "nlu",
+    };
+    // Unknown intents (e.g. from an http NLU service) would grow the label set.
+    let command = match via {
+        "plan" => "plan",
+        // Set by api.rs from its own fixed operation names.
+        "rest" => resolution.intent.as_deref().unwrap_or("unknown"),
+        _ => resolution.intent.as_deref().and_then(registry::find).map_or("unknown", |spec| spec.name),
+    };
+    let status = label(&response.status);
+    let code = response.code.as_ref().map(label).unwrap_or_default();
+
+    let metrics = metrics();
+    metrics.commands.with_label_values(&[command, &status, &code]).inc();
+    metrics.command_duration.with_label_values(&[command]).observe(duration.as_secs_f64());
+    metrics.dispatch.with_label_values(&[via]).inc();
+    if let (Some(confidence), "nlu") = (resolution.confidence, via) {
+        metrics.nlu_confidence.observe(confidence);
+    }
+}
+
+// One call to an NLU service, including its retries.
+pub fn record_nlu_request(backend: &str, result: &Result<crate::nlu::NluResponse, NluError>, duration: Duration) {
+    let outcome = match result {
+        Ok(_) => "ok",
+        Err(NluError::Unavailable(_)) => "unavailable",
+        Err(NluError::Timeout(_)) => "timeout",
+        Err(NluError::Busy(_)) => "busy",
+        Err(NluError::InvalidResponse(_)) => "invalid_response",
+        Err(NluError::Service(_)) => "service_error",
+    };
+    metrics().nlu_duration.with_label_values(&[backend, outcome]).observe(duration.as_secs_f64());
+}
+
+pub fn record_nlu_restart(backend: &str, reason: &str) {
+    metrics().nlu_restarts.with_label_values(&[backend, reason]).inc();
+}
+
+// One call to the IPFS HTTP API; `endpoint` is the API path, e.g. "/add".
+pub fn record_ipfs_request(endpoint: &str, succeeded: bool, duration: Duration) {
+    let metrics = metrics();
+    metrics.ipfs_duration.with_label_values(&[endpoint]).observe(duration.as_secs_f64());
+    if !succeeded {
+        metrics.ipfs_errors.with_label_values(&[endpoint]).inc();
+    }
+}
+
+// ActiveSession: Counts an open WebSocket session until dropped
+pub struct ActiveSession(());
+
+impl ActiveSession {
+    pub fn open() -> Self {
+        metrics().active_sessions.inc();
+        ActiveSession(())
+    }
+}
+
+impl Drop for ActiveSession {
+    fn drop(&mut self) {
+        metrics().active_sessions.dec();
+    }
+}
+
+// The serde name of a status or error code, e.g. "success" or "nlu_timeout".
+fn label(value: &impl serde::Serialize) -> String {
+    serde_json::to_value(value).ok().and_then(|value| value.as_str().map(String::from)).unwrap_or_default()
+}
+
+// --- Endpoint ---
+
+#[utoipa::path(
+    get, path = "/metrics", tag = "monitoring",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", content_type = "text/plain; version=0.0.4", body = String),
+        (status = 401, description = "Missing or invalid API token", body = CommandResponse),
+        (status = 403, description = "The token lacks the metrics:read scope", body = CommandResponse),
+    )
+)]
+pub async fn metrics_endpoint() -> HttpResponse {
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&metrics().registry.gather(), &mut buffer) {
+        tracing::error!("Failed to encode metrics: {}", e);
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok().content_type(encoder.format_type()).body(buffer)
+}
@@ -0,0 +1,462 @@
+// src/nlu/cache.rs for omnimind-core
+// Normalized-utterance LRU cache in front of an NLU backend, so repeated phrases
+// like "show me my files" skip the model. Entries are keyed by the backend's
+// model/label version, can expire after a TTL and can be persisted to disk. The
+// file is rewritten off the async executor a few seconds after a change (batching
+// the misses in between), and once more when the cache is dropped.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use super::{NluBackend, NluError, NluResponse};
+
+// How long after a change the cache file is rewritten.
+const FLUSH_DELAY: Duration = Duration::from_secs(5);
+
+// CacheSettings: Size, expiry and persistence of the NLU cache.
+#[derive(Debug, Clone)]
+pub struct CacheSettings {
+    pub capacity: usize, // 0 disables the cache
+    pub ttl: Option<Duration>,
+    pub file: Option<PathBuf>,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings { capacity: 512, ttl: None, file: None }
+    }
+}
+
+// CacheStats: Counters reported by the `nlu_cache` command.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub expirations: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    version: String,
+    key: String,
+    response: NluResponse,
+    created_unix: i64,
+    #[serde(skip)]
+    last_used: u64,
+}
+
+// The on-disk format: a flat list, most recently used last.
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    entries: Vec<CacheEntry>,
+}
+
+struct CacheInner {
+    entries: HashMap<String, CacheEntry>,
+    tick: u64,
+    stats: CacheStats,
+    dirty: bool, // Changed since the file was last written
+}
+
+pub struct NluCache {
+    settings: CacheSettings,
+    inner: Mutex<CacheInner>,
+    flush_scheduled: AtomicBool,
+    file_lock: Mutex<()>, // Held while writing the file, so flushes cannot interleave
+}
+
+impl NluCache {
+    pub fn new(settings: CacheSettings) -> Self {
+        let cache = NluCache {
+            inner: Mutex::new(CacheInner { entries: HashMap::new(), tick: 0, stats: CacheStats { capacity: settings.capacity, ..Default::default() }, dirty: false }),
+            settings,
+            flush_scheduled: AtomicBool::new(false),
+            file_lock: Mutex::new(()),
+        };
+        cache.load();
+        cache
+    }
+
+    pub fn get(&self, version: &str, text: &str) -> Option<NluResponse> {
+        let key = normalize_utterance(text);
+        let mut inner = self.inner.lock().ok()?;
+        let full_key = composite_key(version, &key);
+        let expired = match inner.entries.get(&full_key) {
+            Some(entry) => self.is_expired(entry),
+            None => {
+                inner.stats.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            inner.entries.remove(&full_key);
+            inner.stats.expirations += 1;
+            inner.stats.misses += 1;
+            return None;
+        }
+
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.stats.hits += 1;
+        let entry = inner.entries.get_mut(&full_key)?;
+        entry.last_used = tick;
+        Some(adapt_to_utterance(&entry.response, text))
+    }
+
+    pub fn insert(&self, version: &str, text: &str, response: &NluResponse) {
+        if self.settings.capacity == 0 {
+            return;
+        }
+        let key = normalize_utterance(text);
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.tick += 1;
+            let entry = CacheEntry {
+                version: version.to_string(),
+                key: key.clone(),
+                response: response.clone(),
+                created_unix: chrono::Utc::now().timestamp(),
+                last_used: inner.tick,
+            };
+            inner.entries.insert(composite_key(version, &key), entry);
+            self.evict_over_capacity(&mut inner);
+            inner.dirty = true;
+        }
+    }
+
+    // Written at once, on the blocking pool: a cleared cache must not come back
+    // after a restart. Needs a Tokio runtime.
+    pub fn clear(self: &Arc<Self>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.entries.clear();
+            inner.dirty = true;
+        }
+        if self.settings.file.is_some() {
+            let cache = Arc::clone(self);
+            tokio::task::spawn_blocking(move || cache.flush());
+        }
+    }
+
+    // Flushes FLUSH_DELAY from now on the blocking pool, unless a flush is already
+    // scheduled. Needs a Tokio runtime.
+    pub fn schedule_flush(self: &Arc<Self>) {
+        if self.settings.file.is_none() || self.flush_scheduled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(FLUSH_DELAY).await;
+            cache.flush_scheduled.store(false, Ordering::SeqCst);
+            let _ = tokio::task::spawn_blocking(move || cache.flush()).await;
+        });
+    }
+
+    // Writes the cache file if anything changed since it was last written. Blocks
+    // on file I/O; only the serialization happens under the cache lock.
+    pub fn flush(&self) {
+        let Some(path) = &self.settings.file else { return };
+        let _file_lock = self.file_lock.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let json = {
+            let Ok(mut inner) = self.inner.lock() else { return };
+            if !inner.dirty {
+                return;
+            }
+            inner.dirty = false;
+            let mut entries: Vec<&CacheEntry> = inner.entries.values().collect();
+            entries.sort_by_key(|entry| entry.last_used);
+            serde_json::to_string(&CacheFile { entries: entries.into_iter().cloned().collect() })
+        };
+
+        let result = json.map_err(std::io::Error::other).and_then(|json| {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, json)?;
+            fs::rename(&tmp_path, path)
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist NLU cache to '{}': {}", path.display(), e);
+            if let Ok(mut inner) = self.inner.lock() {
+                inner.dirty = true;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        match self.inner.lock() {
+            Ok(inner) => CacheStats { entries: inner.entries.len(), ..inner.stats.clone() },
+            Err(_) => CacheStats::default(),
+        }
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        match self.settings.ttl {
+            Some(ttl) => chrono::Utc::now().timestamp() - entry.created_unix > ttl.as_secs() as i64,
+            None => false,
+        }
+    }
+
+    // Drops least recently used entries until we are back under capacity.
+    fn evict_over_capacity(&self, inner: &mut CacheInner) {
+        while inner.entries.len() > self.settings.capacity {
+            let oldest = inner.entries.iter().min_by_key(|(_, entry)| entry.last_used).map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    inner.entries.remove(&key);
+                    inner.stats.evictions += 1;
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn load(&self) {
+        let Some(path) = &self.settings.file else { return };
+        let Ok(contents) = fs::read_to_string(path) else { return };
+        let file: CacheFile = match serde_json::from_str(&contents) {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable NLU cache file '{}': {}", path.display(), e);
+                return;
+            }
+        };
+        if let Ok(mut inner) = self.inner.lock() {
+            for mut entry in file.entries {
+                if self.is_expired(&entry) {
+                    continue;
+                }
+                inner.tick += 1;
+                entry.last_used = inner.tick;
+                inner.entries.insert(composite_key(&entry.version, &entry.key), entry);
+            }
+            self.evict_over_capacity(&mut inner);
+            tracing::info!("Loaded {} NLU cache entries from '{}'.", inner.entries.len(), path.display());
+        }
+    }
+}
+
+impl Drop for NluCache {
+    // Covers the changes since the last scheduled flush, at shutdown or when a
+    // reload replaces the backend.
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+// CachedNluBackend: Wraps any backend with an NluCache.
+pub struct CachedNluBackend {
+    inner: Box<dyn NluBackend>,
+    cache: Arc<NluCache>,
+}
+
+impl CachedNluBackend {
+    pub fn new(inner: Box<dyn NluBackend>, settings: CacheSettings) -> Self {
+        CachedNluBackend { inner, cache: Arc::new(NluCache::new(settings)) }
+    }
+}
+
+#[async_trait]
+impl NluBackend for CachedNluBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn version(&self) -> String {
+        self.inner.version()
+    }
+
+    fn cache(&self) -> Option<Arc<NluCache>> {
+        Some(Arc::clone(&self.cache))
+    }
+
+    async fn ping(&self) -> Result<Option<String>, NluError> {
+        self.inner.ping().await
+    }
+
+    async fn classify(&self, text: &str) -> Result<NluResponse, NluError> {
+        let version = self.inner.version();
+        if let Some(response) = self.cache.get(&version, text) {
+            tracing::debug!("NLU cache hit for '{}'.", text);
+            return Ok(response);
+        }
+        let response = self.inner.classify(text).await?;
+        self.cache.insert(&version, text, &response);
+        self.cache.schedule_flush();
+        Ok(response)
+    }
+}
+
+// Lowercases, collapses whitespace and drops trailing "?"/"!" so trivially
+// different spellings of the same request share one entry.
+pub fn normalize_utterance(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['?', '!'])
+        .trim_end()
+        .to_string()
+}
+
+fn composite_key(version: &str, normalized: &str) -> String {
+    format!("{}\u{1f}{}", version, normalized)
+}
+
+// A hit may come from an utterance that differed in case or spacing. Arguments
+// (note titles, paths) must keep the caller's spelling, so we find where the
+// cached arguments sat in the cached utterance and take the same words from
+// the current one.
+fn adapt_to_utterance(cached: &NluResponse, text: &str) -> NluResponse {
+    let mut response = cached.clone();
+    response.original_text = text.to_string();
+
+    let current: Vec<&str> = text.split_whitespace().collect();
+    let original: Vec<&str> = cached.original_text.split_whitespace().collect();
+    let arguments: Vec<&str> = cached.arguments_text.split_whitespace().collect();
+    if arguments.is_empty() || current.len() != original.len() {
+        return response;
+    }
+    let position = (0..=original.len().saturating_sub(arguments.len())).find(|&start| {
+        original.len() >= start + arguments.len()
+            && original[start..start + arguments.len()].iter().zip(&arguments).all(|(a, b)| a.eq_ignore_ascii_case(b))
+    });
+    if let Some(start) = position {
+        response.arguments_text = current[start..start + arguments.len()].join(" ");
+    }
+    response
+}
+
+// Stable FNV-1a fingerprint used for backend versions (std's hasher may change
+// between Rust releases, which would needlessly invalidate persisted caches).
+pub fn fingerprint(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(text: &str, intent: &str, arguments: &str) -> NluResponse {
+        NluResponse {
+            original_text: text.to_string(),
+            intent: intent.to_string(),
+            predicted_label: intent.to_string(),
+            confidence: 0.9,
+            arguments_text: arguments.to_string(),
+        }
+    }
+
+    fn cache(capacity: usize, ttl: Option<Duration>, file: Option<PathBuf>) -> NluCache {
+        NluCache::new(CacheSettings { capacity, ttl, file })
+    }
+
+    #[test]
+    fn normalizes_case_spacing_and_trailing_punctuation() {
+        assert_eq!(normalize_utterance("  Show ME   my files?! "), "show me my files");
+        assert_eq!(normalize_utterance("what is 2+2?"), "what is 2+2");
+    }
+
+    #[test]
+    fn hits_keep_the_callers_spelling_of_arguments() {
+        let cache = cache(8, None, None);
+        cache.insert("v1", "create a note called Groceries", &response("create a note called Groceries", "create_note", "Groceries"));
+        let hit = cache.get("v1", "Create a  note called GROCERIES?").unwrap();
+        assert_eq!(hit.intent, "create_note");
+        assert_eq!(hit.arguments_text, "GROCERIES?");
+        assert_eq!(hit.original_text, "Create a  note called GROCERIES?");
+    }
+
+    #[test]
+    fn entries_are_keyed_by_backend_version() {
+        let cache = cache(8, None, None);
+        cache.insert("python:aaaa", "list my files", &response("list my files", "ls", ""));
+        assert!(cache.get("python:aaaa", "list my files").is_some());
+        assert!(cache.get("python:bbbb", "list my files").is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = cache(2, None, None);
+        cache.insert("v1", "one", &response("one", "echo", ""));
+        cache.insert("v1", "two", &response("two", "echo", ""));
+        assert!(cache.get("v1", "one").is_some());
+        cache.insert("v1", "three", &response("three", "echo", ""));
+        assert!(cache.get("v1", "two").is_none());
+        assert!(cache.get("v1", "one").is_some());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let cache = cache(8, Some(Duration::from_secs(60)), None);
+        cache.insert("v1", "hello", &response("hello", "echo", ""));
+        if let Ok(mut inner) = cache.inner.lock() {
+            inner.entries.values_mut().for_each(|entry| entry.created_unix -= 120);
+        }
+        assert!(cache.get("v1", "hello").is_none());
+        assert_eq!(cache.stats().expirations, 1);
+    }
+
+    #[test]
+    fn a_zero_capacity_cache_stores_nothing() {
+        let cache = cache(0, None, None);
+        cache.insert("v1", "hello", &response("hello", "echo", ""));
+        assert!(cache.get("v1", "hello").is_none());
+    }
+
+    #[test]
+    fn persisted_entries_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("omnimind-cache-test-{}.json", uuid::Uuid::new_v4()));
+        let first = cache(8, None, Some(path.clone()));
+        first.insert("v1", "list my files", &response("list my files", "ls", ""));
+        first.flush();
+        assert!(path.exists());
+        // Anything not yet flushed is written when the cache is dropped.
+        first.insert("v1", "say hi", &response("say hi", "echo", "hi"));
+        drop(first);
+        let reloaded = cache(8, None, Some(path.clone()));
+        assert_eq!(reloaded.get("v1", "list my files").map(|hit| hit.intent).as_deref(), Some("ls"));
+        assert_eq!(reloaded.get("v1", "say hi").map(|hit| hit.intent).as_deref(), Some("echo"));
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn a_clear_is_written_without_waiting_for_a_flush() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("cache.json");
+        let first = Arc::new(cache(8, None, Some(path.clone())));
+        first.insert("v1", "list my files", &response("list my files", "ls", ""));
+        first.flush();
+        first.clear();
+        // Written on the blocking pool; the cache stays alive so Drop does not write it.
+        for _ in 0..100 {
+            if cache(8, None, Some(path.clone())).stats().entries == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("the cleared cache was not written");
+    }
+
+    #[test]
+    fn fingerprints_are_stable() {
+        assert_eq!(fingerprint(b""), "cbf29ce484222325");
+        assert_ne!(fingerprint(b"labels-a"), fingerprint(b"labels-b"));
+    }
+}
@@ -0,0 +1,268 @@
+// src/nlu/corrections.rs for omnimind-core
+// User-confirmed utterance -> command mappings ("teach" and clarification answers).
+// Consulted before the NLU model, stored locally as JSON, exportable as
+// few-shot examples or fine-tuning data.
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Serialize, Deserialize};
+
+use crate::error::OmniError;
+
+use super::cache::normalize_utterance;
+use super::rules::edit_distance;
+use super::NluResponse;
+
+// Minimum similarity (1 - edit distance / length) for a fuzzy match.
+const FUZZY_SIMILARITY_THRESHOLD: f64 = 0.85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrectionSource {
+    Teach,         // Explicit `teach "<phrase>" as <command>`
+    Clarification, // User answered a low-confidence "did you mean" question
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Correction {
+    pub phrase: String,
+    pub normalized: String,
+    pub command: String, // Full command line, e.g. "ls omni_notes"
+    pub source: CorrectionSource,
+    pub created: String,
+}
+
+impl Correction {
+    fn intent(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or("")
+    }
+
+    fn arguments_text(&self) -> String {
+        self.command.split_whitespace().skip(1).collect::<Vec<_>>().join(" ")
+    }
+}
+
+pub struct CorrectionStore {
+    path: PathBuf,
+    entries: Mutex<Vec<Correction>>,
+}
+
+impl CorrectionStore {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Vec<Correction>>(&contents).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable corrections file '{}': {}", path.display(), e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        if !entries.is_empty() {
+            tracing::info!("Loaded {} taught NLU phrases from '{}'.", entries.len(), path.display());
+        }
+        CorrectionStore { path, entries: Mutex::new(entries) }
+    }
+
+    // Exact (normalized) matches win; otherwise the closest phrase above the threshold.
+    pub fn lookup(&self, text: &str) -> Option<NluResponse> {
+        let normalized = normalize_utterance(text);
+        let entries = self.entries.lock().ok()?;
+
+        let (correction, similarity) = match entries.iter().find(|c| c.normalized == normalized) {
+            Some(exact) => (exact, 1.0),
+            None => entries
+                .iter()
+                .map(|c| (c, similarity(&c.normalized, &normalized)))
+                .filter(|(_, score)| *score >= FUZZY_SIMILARITY_THRESHOLD)
+                .max_by(|a, b| a.1.total_cmp(&b.1))?,
+        };
+
+        let label = if similarity >= 1.0 { "taught" } else { "taught (fuzzy)" };
+        Some(NluResponse {
+            original_text: text.to_string(),
+            intent: correction.intent().to_string(),
+            predicted_label: format!("{}: {}", label, correction.phrase),
+            confidence: similarity,
+            arguments_text: correction.arguments_text(),
+        })
+    }
+
+    // Adds or replaces the mapping for `phrase` and persists the store.
+    pub fn record(&self, phrase: &str, command: &str, source: CorrectionSource) -> Result<Correction, OmniError> {
+        let normalized = normalize_utterance(phrase);
+        if normalized.is_empty() {
+            return Err(OmniError::InvalidArguments("Cannot teach an empty phrase.".to_string()));
+        }
+        let correction = Correction {
+            phrase: phrase.trim().to_string(),
+            normalized: normalized.clone(),
+            command: command.split_whitespace().collect::<Vec<_>>().join(" "),
+            source,
+            created: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        };
+
+        let mut entries = self.entries.lock().map_err(|_| OmniError::Internal("Corrections store lock error".to_string()))?;
+        entries.retain(|c| c.normalized != normalized);
+        entries.push(correction.clone());
+        self.save(&entries)?;
+        Ok(correction)
+    }
+
+    pub fn forget(&self, phrase: &str) -> Result<bool, OmniError> {
+        let normalized = normalize_utterance(phrase);
+        let mut entries = self.entries.lock().map_err(|_| OmniError::Internal("Corrections store lock error".to_string()))?;
+        let before = entries.len();
+        entries.retain(|c| c.normalized != normalized);
+        if entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&entries)?;
+        Ok(true)
+    }
+
+    pub fn list(&self) -> Vec<Correction> {
+        self.entries.lock().map(|entries| entries.clone()).unwrap_or_default()
+    }
+
+    // Few-shot examples: [{ "text", "intent", "arguments_text" }].
+    pub fn export_few_shot(&self) -> serde_json::Value {
+        let examples: Vec<serde_json::Value> = self.list().iter().map(|c| serde_json::json!({
+            "text": c.phrase,
+            "intent": c.intent(),
+            "arguments_text": c.arguments_text(),
+        })).collect();
+        serde_json::Value::Array(examples)
+    }
+
+    // Fine-tuning data: one {"prompt", "completion"} JSON object per line.
+    pub fn export_jsonl(&self) -> String {
+        self.list().iter()
+            .map(|c| serde_json::json!({ "prompt": c.phrase, "completion": c.command }).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn save(&self, entries: &[Correction]) -> Result<(), OmniError> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| OmniError::io(format!("Error creating directory '{}'", parent.display()), e))?;
+        }
+        let json = serde_json::to_string_pretty(entries).map_err(|e| OmniError::Internal(format!("Error serializing corrections: {}", e)))?;
+        fs::write(&self.path, json).map_err(|e| OmniError::io(format!("Error writing corrections file '{}'", self.path.display()), e))
+    }
+}
+
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn store(dir: &TempDir) -> CorrectionStore {
+        CorrectionStore::load(dir.path().join("corrections.json"))
+    }
+
+    #[test]
+    fn exact_matches_ignore_case_spacing_and_trailing_punctuation() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        store.record("Show my Groceries", "read_note groceries", CorrectionSource::Teach).unwrap();
+
+        let response = store.lookup("  show MY   groceries?").unwrap();
+        assert_eq!(response.intent, "read_note");
+        assert_eq!(response.arguments_text, "groceries");
+        assert_eq!(response.confidence, 1.0);
+        assert_eq!(response.predicted_label, "taught: Show my Groceries");
+    }
+
+    #[test]
+    fn fuzzy_matches_need_the_similarity_threshold() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        store.record("show my grocery list", "read_note groceries", CorrectionSource::Teach).unwrap();
+
+        // One edit in 20 characters: 0.95.
+        let response = store.lookup("show my grocery lust").unwrap();
+        assert_eq!(response.predicted_label, "taught (fuzzy): show my grocery list");
+        assert!((response.confidence - 0.95).abs() < 1e-9);
+        // Four edits: 0.8, below the threshold.
+        assert!(store.lookup("show my gro list").is_none());
+    }
+
+    #[test]
+    fn the_closest_phrase_wins_and_an_exact_match_beats_any_fuzzy_one() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        store.record("list my notes now", "ls omni_notes", CorrectionSource::Teach).unwrap();
+        store.record("list my nodes now", "ls /srv/nodes", CorrectionSource::Clarification).unwrap();
+
+        assert_eq!(store.lookup("list my nodes now").unwrap().arguments_text, "/srv/nodes");
+        assert_eq!(store.lookup("list my notes nov").unwrap().arguments_text, "omni_notes");
+    }
+
+    #[test]
+    fn recording_replaces_the_phrase_and_persists_across_loads() {
+        let dir = TempDir::new().unwrap();
+        let first = store(&dir);
+        first.record("my stuff", "ls  omni_notes", CorrectionSource::Teach).unwrap();
+        first.record("My Stuff", "ls /tmp", CorrectionSource::Teach).unwrap();
+        assert!(first.record("  ", "ls", CorrectionSource::Teach).is_err());
+
+        let reloaded = store(&dir);
+        let corrections = reloaded.list();
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].command, "ls /tmp");
+        assert_eq!(corrections[0].source, CorrectionSource::Teach);
+    }
+
+    #[test]
+    fn forgetting_removes_the_phrase_from_the_file() {
+        let dir = TempDir::new().unwrap();
+        let first = store(&dir);
+        first.record("my stuff", "ls omni_notes", CorrectionSource::Teach).unwrap();
+        assert!(!first.forget("someone else's stuff").unwrap());
+        assert!(first.forget("MY STUFF").unwrap());
+
+        let reloaded = store(&dir);
+        assert!(reloaded.list().is_empty());
+        assert!(reloaded.lookup("my stuff").is_none());
+    }
+
+    #[test]
+    fn an_unreadable_file_loads_as_empty() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("corrections.json"), "not json").unwrap();
+        assert!(store(&dir).list().is_empty());
+    }
+
+    #[test]
+    fn exports_split_the_command_into_intent_and_arguments() {
+        let dir = TempDir::new().unwrap();
+        let store = store(&dir);
+        store.record("my stuff", "ls omni_notes", CorrectionSource::Teach).unwrap();
+        store.record("whats new", "list_notes", CorrectionSource::Clarification).unwrap();
+
+        assert_eq!(
+            store.export_few_shot(),
+            serde_json::json!([
+                { "text": "my stuff", "intent": "ls", "arguments_text": "omni_notes" },
+                { "text": "whats new", "intent": "list_notes", "arguments_text": "" },
+            ])
+        );
+        let lines: Vec<serde_json::Value> = store.export_jsonl().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(
+            lines,
+            [
+                serde_json::json!({ "prompt": "my stuff", "completion": "ls omni_notes" }),
+                serde_json::json!({ "prompt": "whats new", "completion": "list_notes" }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+// src/nlu/http.rs for omnimind-core
+// NLU backend that POSTs utterances to a remote or containerized classifier.
+// Request body: { "text": "<utterance>" }, with the command's request ID in
+// X-Request-Id. Response body: an NluResponse object. /readyz pings with a GET
+// on the same URL.
+use async_trait::async_trait;
+
+use crate::limits::NluPermit;
+use crate::logging;
+
+use super::{call_with_policy, parse_ping, AttemptError, NluBackend, NluCallPolicy, NluError, NluResponse, Permits};
+
+pub struct HttpNluBackend {
+    url: String,
+    model_version: Option<String>,
+    client: reqwest::Client,
+    policy: NluCallPolicy,
+}
+
+impl HttpNluBackend {
+    pub fn new(url: &str, model_version: Option<String>, policy: NluCallPolicy) -> Self {
+        tracing::info!("Using HTTP NLU service at {}", url);
+        HttpNluBackend { url: url.to_string(), model_version, client: reqwest::Client::new(), policy }
+    }
+
+    async fn request(&self, text: &str) -> Result<String, AttemptError> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({ "text": text }));
+        if let Some(request_id) = logging::request_id() {
+            request = request.header(logging::REQUEST_ID_HEADER.as_str(), request_id);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AttemptError::Transient(NluError::Unavailable(format!("Failed to connect to NLU service at {}: {}", self.url, e))))?;
+
+        let status_code = response.status();
+        let body = response.text().await
+            .map_err(|e| AttemptError::Transient(NluError::Unavailable(format!("Failed to read NLU service response: {}", e))))?;
+        if status_code.is_server_error() {
+            return Err(AttemptError::Transient(NluError::Service(format!("NLU service failed with {}: {}", status_code, body))));
+        }
+        if !status_code.is_success() {
+            return Err(AttemptError::Fatal(NluError::Unavailable(format!("NLU service rejected the request with {}: {}", status_code, body))));
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl NluBackend for HttpNluBackend {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    // Without an explicit model version, a different service URL is treated as a different model.
+    fn version(&self) -> String {
+        match &self.model_version {
+            Some(version) => format!("http:{}", version),
+            None => format!("http:{}", self.url),
+        }
+    }
+
+    async fn classify(&self, text: &str) -> Result<NluResponse, NluError> {
+        call_with_policy(self.name(), self.policy, Permits::PerCall, || self.request(text)).await
+    }
+
+    // Any answer short of a server error means the service is up; nlu_server.py
+    // also names its model, other services may not support GET at all (405, or
+    // 501 from Python's http.server).
+    async fn ping(&self) -> Result<Option<String>, NluError> {
+        let _permit = NluPermit::acquire()?;
+        let mut request = self.client.get(&self.url);
+        if let Some(request_id) = logging::request_id() {
+            request = request.header(logging::REQUEST_ID_HEADER.as_str(), request_id);
+        }
+        let response = request.send().await.map_err(|e| NluError::Unavailable(format!("Failed to connect to NLU service at {}: {}", self.url, e)))?;
+        let status_code = response.status();
+        if status_code.is_server_error() && status_code != reqwest::StatusCode::NOT_IMPLEMENTED {
+            return Err(NluError::Service(format!("NLU service failed with {}", status_code)));
+        }
+        let body = response.text().await.unwrap_or_default();
+        Ok(if status_code.is_success() { parse_ping(&body).unwrap_or(None) } else { None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::config::{self, Config};
+
+    const CLASSIFIED: &str = r#"{"original_text": "list files in /tmp", "intent": "list_files", "predicted_label": "list files", "confidence": 0.93, "arguments_text": "in /tmp"}"#;
+
+    // StubReply: What the stub server sends back for one request
+    struct StubReply {
+        status: &'static str, // e.g. "200 OK"
+        body: &'static str,
+        delay: Duration,
+    }
+
+    fn reply(status: &'static str, body: &'static str) -> StubReply {
+        StubReply { status, body, delay: Duration::ZERO }
+    }
+
+    // Serves `replies` in order, one per connection, on a local port. Returns the
+    // service URL and a count of the requests it received.
+    fn stub_server(replies: Vec<StubReply>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/classify", listener.local_addr().unwrap());
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&received);
+        std::thread::spawn(move || {
+            for (stream, reply) in listener.incoming().zip(replies) {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                std::thread::sleep(reply.delay);
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    reply.status, reply.body.len(), reply.body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (url, received)
+    }
+
+    // Calls take NLU permits, so each test holds the config guard; another test may
+    // be counting them.
+    fn backend(url: &str, timeout: Duration, retries: u32) -> HttpNluBackend {
+        HttpNluBackend::new(url, None, NluCallPolicy { timeout, retries })
+    }
+
+    #[tokio::test]
+    async fn classifies_with_the_service_answer() {
+        let _config = config::testing::install(Config::default()).await;
+        let (url, received) = stub_server(vec![reply("200 OK", CLASSIFIED)]);
+        let response = backend(&url, Duration::from_secs(5), 0).classify("list files in /tmp").await.unwrap();
+        assert_eq!(response.intent, "list_files");
+        assert_eq!(response.arguments_text, "in /tmp");
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn a_slow_service_times_out() {
+        let _config = config::testing::install(Config::default()).await;
+        let slow = StubReply { status: "200 OK", body: CLASSIFIED, delay: Duration::from_secs(2) };
+        let (url, _) = stub_server(vec![slow]);
+        let error = backend(&url, Duration::from_millis(200), 0).classify("list files").await.unwrap_err();
+        assert!(matches!(error, NluError::Timeout(_)), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn a_server_error_is_retried() {
+        let _config = config::testing::install(Config::default()).await;
+        let (url, received) = stub_server(vec![reply("503 Service Unavailable", "model loading"), reply("200 OK", CLASSIFIED)]);
+        let response = backend(&url, Duration::from_secs(5), 1).classify("list files in /tmp").await.unwrap();
+        assert_eq!(response.intent, "list_files");
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn a_client_error_is_not_retried() {
+        let _config = config::testing::install(Config::default()).await;
+        let (url, received) = stub_server(vec![reply("400 Bad Request", "no text"), reply("200 OK", CLASSIFIED)]);
+        let error = backend(&url, Duration::from_secs(5), 1).classify("list files").await.unwrap_err();
+        assert!(matches!(error, NluError::Unavailable(_)), "{:?}", error);
+        assert_eq!(received.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_json_is_rejected() {
+        let _config = config::testing::install(Config::default()).await;
+        let (url, _) = stub_server(vec![reply("200 OK", "<html>not json</html>")]);
+        let error = backend(&url, Duration::from_secs(5), 0).classify("list files").await.unwrap_err();
+        assert!(matches!(error, NluError::InvalidResponse(_)), "{:?}", error);
+    }
+}
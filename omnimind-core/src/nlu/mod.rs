@@ -0,0 +1,250 @@
+// src/nlu/mod.rs for omnimind-core
+// NLU backends: turn free-form text into one of our command intents.
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+
+use crate::config::NluConfig;
+use crate::limits::NluPermit;
+use crate::metrics;
+
+pub mod cache;
+pub mod corrections;
+pub mod http;
+pub mod python;
+pub mod rules;
+
+pub use cache::{CacheSettings, CachedNluBackend, NluCache};
+pub use http::HttpNluBackend;
+pub use python::PythonNluBackend;
+pub use rules::RuleBasedNluBackend;
+
+// NluResponse: Expected JSON structure from every NLU backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NluResponse {
+    pub original_text: String,
+    pub intent: String,
+    pub predicted_label: String,
+    pub confidence: f64,
+    pub arguments_text: String,
+}
+
+// NluError: Why a backend could not classify an utterance
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum NluError {
+    #[error("{0}")]
+    Unavailable(String),     // Process gone, connection refused, request rejected
+    #[error("{0}")]
+    Timeout(String),         // No answer within the call policy's timeout
+    #[error("{0}")]
+    Busy(String),            // limits.nlu_max_concurrent requests already in flight
+    #[error("{0}")]
+    InvalidResponse(String), // Answer was not a well-formed NluResponse
+    #[error("{0}")]
+    Service(String),         // The service answered with an explicit error
+}
+
+// NluBackend: Anything that can classify an utterance into an intent.
+#[async_trait]
+pub trait NluBackend: Send + Sync {
+    /// Short name used in logs and responses ("python", "http", "rules").
+    fn name(&self) -> &str;
+
+    /// Identifies the model and label set; cached results from another version are ignored.
+    fn version(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// The result cache in front of this backend, if any.
+    fn cache(&self) -> Option<Arc<NluCache>> {
+        None
+    }
+
+    /// Classifies one utterance.
+    async fn classify(&self, text: &str) -> Result<NluResponse, NluError>;
+
+    /// Checks that the backend answers (for /readyz); returns the loaded model's
+    /// name when the backend reports one.
+    async fn ping(&self) -> Result<Option<String>, NluError> {
+        Ok(None)
+    }
+}
+
+// NluBackendKind: Which backend the server should run with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NluBackendKind {
+    Python,
+    Http,
+    Rules,
+    None, // Keyword-only mode: no NLU fallback at all
+}
+
+impl NluBackendKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "python" => Some(NluBackendKind::Python),
+            "http" => Some(NluBackendKind::Http),
+            "rules" | "rust" | "builtin" => Some(NluBackendKind::Rules),
+            "none" | "off" | "keyword" => Some(NluBackendKind::None),
+            _ => None,
+        }
+    }
+}
+
+// NluCallPolicy: Timeout and retry budget shared by the out-of-process backends.
+#[derive(Debug, Clone, Copy)]
+pub struct NluCallPolicy {
+    pub timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for NluCallPolicy {
+    fn default() -> Self {
+        // Zero-shot classification on CPU can take a few seconds per request.
+        NluCallPolicy { timeout: Duration::from_secs(30), retries: 1 }
+    }
+}
+
+// NluSettings: Everything needed to construct the configured backend.
+#[derive(Debug, Clone)]
+pub struct NluSettings {
+    pub kind: NluBackendKind,
+    pub python_executable: String,
+    pub script_path: String,
+    pub http_url: String,
+    pub model_version: Option<String>, // Version label for the http backend's model
+    pub policy: NluCallPolicy,
+    pub cache: CacheSettings,
+}
+
+impl NluSettings {
+    // `config` has been validated (config.rs), so the backend name is known.
+    pub fn from_config(config: &NluConfig) -> Self {
+        NluSettings {
+            kind: NluBackendKind::parse(&config.backend).unwrap_or(NluBackendKind::Python),
+            python_executable: config.python.clone(),
+            script_path: config.script.display().to_string(),
+            http_url: config.url.clone(),
+            model_version: config.model_version.clone(),
+            policy: NluCallPolicy { timeout: Duration::from_millis(config.timeout_ms), retries: config.retries },
+            cache: CacheSettings {
+                capacity: config.cache_size,
+                ttl: config.cache_ttl_secs.map(Duration::from_secs),
+                file: config.cache_file.clone(),
+            },
+        }
+    }
+}
+
+// Builds the configured backend. If the Python process cannot be spawned we
+// fall back to the built-in rules instead of refusing to start. The model-backed
+// backends get a result cache in front of them; the rules are cheap enough without.
+pub fn build_backend(settings: &NluSettings) -> Option<Box<dyn NluBackend>> {
+    let backend: Box<dyn NluBackend> = match settings.kind {
+        NluBackendKind::Python => match PythonNluBackend::spawn(&settings.python_executable, &settings.script_path, settings.policy) {
+            Ok(backend) => Box::new(backend),
+            Err(e) => {
+                tracing::error!("Failed to spawn Python NLU script: {}", e);
+                tracing::error!("Ensure Python is installed ('{}') and accessible via PATH.", settings.python_executable);
+                tracing::error!("Ensure the script exists at relative path: '{}' from where omnimind-core is run.", settings.script_path);
+                tracing::warn!("Falling back to the built-in rule-based NLU backend.");
+                return Some(Box::new(RuleBasedNluBackend::new()));
+            }
+        },
+        NluBackendKind::Http => Box::new(HttpNluBackend::new(&settings.http_url, settings.model_version.clone(), settings.policy)),
+        NluBackendKind::Rules => return Some(Box::new(RuleBasedNluBackend::new())),
+        NluBackendKind::None => return None,
+    };
+
+    if settings.cache.capacity == 0 {
+        return Some(backend);
+    }
+    Some(Box::new(CachedNluBackend::new(backend, settings.cache.clone())))
+}
+
+// --- Shared plumbing for out-of-process backends ---
+
+// AttemptError: Why a single request to an NLU service failed.
+pub(crate) enum AttemptError {
+    Transient(NluError), // Worth retrying (timeout, connection refused, 5xx)
+    Fatal(NluError),     // Retrying will not help (broken pipe, 4xx)
+}
+
+// Permits: Where a call's limits.nlu_max_concurrent permit is held
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Permits {
+    PerCall,    // call_with_policy holds one; an attempt stops working once it is dropped
+    PerAttempt, // Each attempt takes its own, since its work can outlive the timeout
+}
+
+// Runs `attempt` under the policy's timeout, retrying transient failures with a
+// short backoff, then validates the raw JSON the service returned. Calls beyond
+// limits.nlu_max_concurrent fail with Busy. The whole call is timed for
+// omnimind_nlu_request_duration_seconds.
+pub(crate) async fn call_with_policy<F, Fut>(backend_name: &str, policy: NluCallPolicy, permits: Permits, mut attempt: F) -> Result<NluResponse, NluError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<String, AttemptError>>,
+{
+    let started = Instant::now();
+    let result = async {
+        let _permit = match permits {
+            Permits::PerCall => Some(NluPermit::acquire()?),
+            Permits::PerAttempt => None,
+        };
+        let mut last_error = NluError::Unavailable(format!("{} NLU backend was not called.", backend_name));
+        for attempt_number in 0..=policy.retries {
+            if attempt_number > 0 {
+                tracing::warn!("Retrying {} NLU request (attempt {} of {}): {}", backend_name, attempt_number + 1, policy.retries + 1, last_error);
+                tokio::time::sleep(Duration::from_millis(200 * attempt_number as u64)).await;
+            }
+            match tokio::time::timeout(policy.timeout, attempt()).await {
+                Ok(Ok(raw)) => return parse_response(&raw),
+                Ok(Err(AttemptError::Fatal(error))) => return Err(error),
+                Ok(Err(AttemptError::Transient(error))) => last_error = error,
+                Err(_) => last_error = NluError::Timeout(format!("NLU service did not answer within {} ms.", policy.timeout.as_millis())),
+            }
+        }
+        Err(last_error)
+    }
+    .await;
+    metrics::record_nlu_request(backend_name, &result, started.elapsed());
+    result
+}
+
+// Reads a ping answer, {"status": "ok", "model": "<name>"}.
+pub(crate) fn parse_ping(raw: &str) -> Result<Option<String>, NluError> {
+    let value: serde_json::Value = serde_json::from_str(raw.trim()).map_err(|e| NluError::InvalidResponse(format!("Unexpected answer to a ping: {}", e)))?;
+    if let Some(error) = value.get("error") {
+        let message = error.as_str().map(String::from).unwrap_or_else(|| error.to_string());
+        return Err(NluError::Service(format!("NLU service reported an error: {}", message)));
+    }
+    Ok(value.get("model").and_then(|model| model.as_str()).map(String::from))
+}
+
+// Parses and sanity-checks one JSON response line/body from an NLU service.
+pub(crate) fn parse_response(raw: &str) -> Result<NluResponse, NluError> {
+    let value: serde_json::Value = serde_json::from_str(raw.trim()).map_err(|e| {
+        tracing::warn!("Failed to parse NLU JSON response: {}. Raw: '{}'", e, raw.trim());
+        NluError::InvalidResponse("Error processing NLU response.".to_string())
+    })?;
+    if let Some(error) = value.get("error") {
+        let message = error.as_str().map(String::from).unwrap_or_else(|| error.to_string());
+        return Err(NluError::Service(format!("NLU service reported an error: {}", message)));
+    }
+
+    let response: NluResponse = serde_json::from_value(value).map_err(|e| {
+        tracing::warn!("NLU JSON response has the wrong shape: {}. Raw: '{}'", e, raw.trim());
+        NluError::InvalidResponse("Error processing NLU response.".to_string())
+    })?;
+    if response.intent.trim().is_empty() {
+        return Err(NluError::InvalidResponse("NLU response is missing an intent.".to_string()));
+    }
+    if !response.confidence.is_finite() || !(0.0..=1.0).contains(&response.confidence) {
+        return Err(NluError::InvalidResponse(format!("NLU response has an invalid confidence: {}", response.confidence)));
+    }
+    Ok(response)
+}
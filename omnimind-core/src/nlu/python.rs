@@ -0,0 +1,291 @@
+// src/nlu/python.rs for omnimind-core
+// NLU backend that talks to omnimind-nlu-py/nlu_server.py over stdin/stdout.
+// Each request is one JSON line, {"text": ..., "request_id": ...}, or
+// {"ping": true} from /readyz; the script's standard error is logged under the
+// `nlu` target.
+use std::io::{Write, BufReader, BufRead};
+use std::process::{Command as OsCommand, Stdio, Child, ChildStderr, ChildStdin, ChildStdout};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::events::{self, CoreEvent};
+use crate::limits::NluPermit;
+use crate::logging;
+use crate::metrics;
+
+use super::cache::fingerprint;
+use super::{call_with_policy, parse_ping, AttemptError, NluBackend, NluCallPolicy, NluError, NluResponse, Permits};
+
+// Both pipes live behind one lock so a request/response pair is never interleaved.
+// A request that timed out keeps the lock until its late answer is read, so the
+// next request never picks up a stale line.
+struct PythonPipes {
+    stdin: ChildStdin,
+    stdout_reader: BufReader<ChildStdout>,
+}
+
+// A script that keeps dying (e.g. missing model dependencies) is not respawned
+// more often than this.
+const MIN_RESTART_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct PythonNluBackend {
+    pipes: Arc<Mutex<PythonPipes>>,
+    child: Mutex<Option<Child>>, // Taken by Drop
+    policy: NluCallPolicy,
+    python_executable: String,
+    script_path: String,
+    script_fingerprint: String, // Changes whenever the model name or labels in the script change
+    last_restart: Mutex<Option<Instant>>,
+}
+
+impl PythonNluBackend {
+    pub fn spawn(python_executable: &str, nlu_script_path: &str, policy: NluCallPolicy) -> std::io::Result<Self> {
+        let script_fingerprint = fingerprint(&std::fs::read(nlu_script_path).unwrap_or_default());
+        let (child, pipes) = spawn_process(python_executable, nlu_script_path)?;
+
+        Ok(PythonNluBackend {
+            pipes: Arc::new(Mutex::new(pipes)),
+            child: Mutex::new(Some(child)),
+            policy,
+            python_executable: python_executable.to_string(),
+            script_path: nlu_script_path.to_string(),
+            script_fingerprint,
+            last_restart: Mutex::new(None),
+        })
+    }
+
+    // Replaces a script process that has exited. Returns false if it is still
+    // running, was restarted too recently, or could not be spawned again.
+    fn restart_if_exited(&self) -> bool {
+        let Ok(mut child) = self.child.lock() else { return false };
+        let Some(child) = child.as_mut() else { return false };
+        let status = match child.try_wait() {
+            Ok(Some(status)) => status,
+            _ => return false,
+        };
+        let Ok(mut last_restart) = self.last_restart.lock() else { return false };
+        if last_restart.is_some_and(|at| at.elapsed() < MIN_RESTART_INTERVAL) {
+            return false;
+        }
+        *last_restart = Some(Instant::now());
+
+        tracing::warn!("Python NLU script exited ({}); restarting it.", status);
+        let (new_child, new_pipes) = match spawn_process(&self.python_executable, &self.script_path) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                tracing::error!("Failed to restart Python NLU script: {}", e);
+                return false;
+            }
+        };
+        match self.pipes.lock() {
+            Ok(mut pipes) => *pipes = new_pipes,
+            Err(_) => return false,
+        }
+        *child = new_child;
+        metrics::record_nlu_restart("python", "exited");
+        events::publish(CoreEvent::NluBackendRestarted { backend: "python".to_string(), reason: format!("script exited ({})", status) });
+        true
+    }
+
+    // One blocking write/read round trip, run off the async executor. The permit
+    // is held until the pipe is free again, not just until the caller stops
+    // waiting, so a hung script cannot park more than nlu_max_concurrent threads.
+    async fn round_trip(&self, line: String, permit: NluPermit) -> Result<String, AttemptError> {
+        let pipes = Arc::clone(&self.pipes);
+        let task = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let mut pipes = pipes.lock().map_err(|_| AttemptError::Fatal(NluError::Unavailable("NLU pipe lock error".to_string())))?;
+
+            if writeln!(pipes.stdin, "{}", line).is_err() || pipes.stdin.flush().is_err() {
+                tracing::warn!("Failed to write/flush to Python NLU stdin.");
+                return Err(AttemptError::Fatal(NluError::Unavailable("Failed to send command to NLU service.".to_string())));
+            }
+
+            let mut nlu_response_json_str = String::new();
+            match pipes.stdout_reader.read_line(&mut nlu_response_json_str) {
+                Ok(0) => Err(AttemptError::Fatal(NluError::Unavailable("NLU service closed its output (did the Python script exit?).".to_string()))),
+                Ok(_) => Ok(nlu_response_json_str),
+                Err(e) => {
+                    tracing::warn!("Failed to read from Python NLU stdout: {}", e);
+                    Err(AttemptError::Transient(NluError::Unavailable("Failed to receive response from NLU service.".to_string())))
+                }
+            }
+        });
+        task.await.map_err(|e| AttemptError::Fatal(NluError::Unavailable(format!("NLU worker task failed: {}", e))))?
+    }
+}
+
+#[async_trait]
+impl NluBackend for PythonNluBackend {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn version(&self) -> String {
+        format!("python:{}", self.script_fingerprint)
+    }
+
+    async fn classify(&self, text: &str) -> Result<NluResponse, NluError> {
+        let line = serde_json::json!({ "text": text, "request_id": logging::request_id() }).to_string();
+        let attempt = || async {
+            let permit = NluPermit::acquire().map_err(AttemptError::Fatal)?;
+            self.round_trip(line.clone(), permit).await
+        };
+        match call_with_policy(self.name(), self.policy, Permits::PerAttempt, attempt).await {
+            // The script died (crash, OOM, killed): start a fresh one and try once more.
+            Err(NluError::Unavailable(_)) if self.restart_if_exited() => call_with_policy(self.name(), self.policy, Permits::PerAttempt, attempt).await,
+            result => result,
+        }
+    }
+
+    // Answered once the model has loaded, after any request already in progress.
+    // Counts against limits.nlu_max_concurrent like a classify call.
+    async fn ping(&self) -> Result<Option<String>, NluError> {
+        let line = serde_json::json!({ "ping": true, "request_id": logging::request_id() }).to_string();
+        let ping = || async {
+            let raw = self.round_trip(line.clone(), NluPermit::acquire()?).await.map_err(|error| match error {
+                AttemptError::Transient(error) | AttemptError::Fatal(error) => error,
+            })?;
+            parse_ping(&raw)
+        };
+        match ping().await {
+            Err(NluError::Unavailable(_)) if self.restart_if_exited() => ping().await,
+            result => result,
+        }
+    }
+}
+
+impl Drop for PythonNluBackend {
+    // The last reference is often dropped on an async worker (a request that
+    // outlived a reload), so the script is shut down on a thread of its own: asked
+    // to exit cleanly, given a moment, then killed and reaped.
+    fn drop(&mut self) {
+        let Some(mut child) = self.child.get_mut().ok().and_then(Option::take) else { return };
+        let pipes = Arc::clone(&self.pipes);
+        std::thread::spawn(move || {
+            // A request still waiting on the script holds the pipes; it gets killed instead.
+            if let Ok(mut pipes) = pipes.try_lock() {
+                let _ = writeln!(pipes.stdin, "__EXIT__");
+                let _ = pipes.stdin.flush();
+            }
+            for _ in 0..20 {
+                if !matches!(child.try_wait(), Ok(None)) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+    }
+}
+
+fn spawn_process(python_executable: &str, nlu_script_path: &str) -> std::io::Result<(Child, PythonPipes)> {
+    tracing::info!("Attempting to spawn Python NLU script: {} {}", python_executable, nlu_script_path);
+    let mut child = OsCommand::new(python_executable)
+        .arg(nlu_script_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    tracing::info!("Python NLU script spawned successfully (PID: {}).", child.id());
+
+    let stdin = child.stdin.take().expect("Failed to open Python stdin pipe");
+    let stdout = child.stdout.take().expect("Failed to open Python stdout pipe");
+    let stderr = child.stderr.take().expect("Failed to open Python stderr pipe");
+    let pid = child.id();
+    // Ends by itself when the script exits and the pipe closes.
+    std::thread::spawn(move || forward_stderr(stderr, pid));
+    Ok((child, PythonPipes { stdin, stdout_reader: BufReader::new(stdout) }))
+}
+
+// Logs each line the script writes to standard error. Lines from its log()
+// helper, "NLU_SERVER_LOG: <LEVEL> [<request id>] message", keep their level and
+// request ID; anything else (tracebacks, library warnings) is logged as a warning.
+fn forward_stderr(stderr: ChildStderr, pid: u32) {
+    for line in BufReader::new(stderr).lines() {
+        let Ok(line) = line else { break };
+        let Some(logged) = line.strip_prefix("NLU_SERVER_LOG:") else {
+            tracing::warn!(target: "nlu", pid, "{}", line);
+            continue;
+        };
+        let (level, rest) = match logged.trim_start().split_once(' ') {
+            Some((level @ ("DEBUG" | "INFO" | "WARNING" | "ERROR"), rest)) => (level, rest),
+            _ => ("INFO", logged.trim_start()),
+        };
+        let (request_id, message) = match rest.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+            Some((request_id, message)) => (Some(tracing::field::display(request_id)), message),
+            None => (None, rest),
+        };
+        match level {
+            "DEBUG" => tracing::debug!(target: "nlu", pid, request_id, "{}", message),
+            "WARNING" => tracing::warn!(target: "nlu", pid, request_id, "{}", message),
+            "ERROR" => tracing::error!(target: "nlu", pid, request_id, "{}", message),
+            _ => tracing::info!(target: "nlu", pid, request_id, "{}", message),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::config::{self, Config};
+
+    // A shell script standing in for nlu_server.py.
+    fn script(body: &str) -> NamedTempFile {
+        let mut script = NamedTempFile::new().unwrap();
+        writeln!(script, "{}", body).unwrap();
+        script
+    }
+
+    // Answers each line after `delay_secs`, and exits on __EXIT__.
+    fn slow_script(delay_secs: f64) -> NamedTempFile {
+        script(&format!(
+            r#"while read -r line; do [ "$line" = "__EXIT__" ] && exit 0; sleep {}; echo '{{"original_text": "hi", "intent": "echo", "predicted_label": "echo", "confidence": 0.9, "arguments_text": ""}}'; done"#,
+            delay_secs
+        ))
+    }
+
+    fn is_running(pid: u32) -> bool {
+        OsCommand::new("kill").args(["-0", &pid.to_string()]).stderr(Stdio::null()).status().is_ok_and(|status| status.success())
+    }
+
+    #[tokio::test]
+    async fn a_timed_out_request_keeps_its_slot_until_the_pipe_is_free() {
+        let mut limited = Config::default();
+        limited.limits.nlu_max_concurrent = 1;
+        let _config = config::testing::install(limited).await;
+        let script = slow_script(1.0);
+        let policy = NluCallPolicy { timeout: Duration::from_millis(100), retries: 0 };
+        let backend = PythonNluBackend::spawn("sh", script.path().to_str().unwrap(), policy).unwrap();
+
+        assert!(matches!(backend.classify("hi").await, Err(NluError::Timeout(_))));
+        // The script is still answering, so the next request is turned away rather than queued.
+        assert!(matches!(backend.classify("hi").await, Err(NluError::Busy(_))));
+        assert!(NluPermit::acquire().is_err());
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(NluPermit::acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn dropping_the_backend_does_not_wait_for_the_script() {
+        // Ignores __EXIT__, so it has to be killed.
+        let script = script("while read -r line; do :; done");
+        let backend = PythonNluBackend::spawn("sh", script.path().to_str().unwrap(), NluCallPolicy::default()).unwrap();
+        let pid = backend.child.lock().unwrap().as_ref().unwrap().id();
+
+        let started = Instant::now();
+        drop(backend);
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(is_running(pid));
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert!(!is_running(pid));
+    }
+}
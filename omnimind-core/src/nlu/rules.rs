@@ -0,0 +1,328 @@
+// src/nlu/rules.rs for omnimind-core
+// Built-in rule-based NLU backend: pattern templates, keyword synonyms and
+// fuzzy (typo-tolerant) keyword matching. No Python or model download needed.
+use async_trait::async_trait;
+
+use super::{NluBackend, NluError, NluResponse};
+
+// Confidence reported for each kind of match. Templates are the most specific.
+const TEMPLATE_CONFIDENCE: f64 = 0.95;
+const SYNONYM_CONFIDENCE: f64 = 0.8;
+const FUZZY_CONFIDENCE: f64 = 0.6;
+
+// Pattern templates: literal words plus at most one `{args}` slot that captures
+// one or more words. A template has to cover the whole utterance.
+const TEMPLATES: &[(&str, &str)] = &[
+    ("create_note", "create a note called {args}"),
+    ("create_note", "create a note named {args}"),
+    ("create_note", "create a note titled {args}"),
+    ("create_note", "create a new note called {args}"),
+    ("create_note", "make a note called {args}"),
+    ("create_note", "make a note named {args}"),
+    ("create_note", "write a note about {args}"),
+    ("create_note", "new note {args}"),
+    ("create_note", "note down {args}"),
+    ("ls", "list files in {args}"),
+    ("ls", "list the files in {args}"),
+    ("ls", "show files in {args}"),
+    ("ls", "show me the files in {args}"),
+    ("ls", "what is in {args}"),
+    ("ls", "what's in {args}"),
+    ("ls", "show me my files"),
+    ("ls", "what files do i have"),
+    ("ipfs_add", "add {args} to ipfs"),
+    ("ipfs_add", "upload {args} to ipfs"),
+    ("ipfs_add", "put {args} on ipfs"),
+    ("ipfs_add", "share {args} on ipfs"),
+    ("ipfs_add", "pin {args}"),
+    ("ipfs_cat", "get {args} from ipfs"),
+    ("ipfs_cat", "download {args} from ipfs"),
+    ("ipfs_cat", "fetch {args} from ipfs"),
+    ("ipfs_cat", "read {args} from ipfs"),
+    ("ipfs_cat", "show {args} from ipfs"),
+    ("ipfs_id", "what is my ipfs id"),
+    ("ipfs_id", "what is my peer id"),
+    ("ipfs_id", "show my ipfs id"),
+    ("ipfs_id", "show my node id"),
+    ("echo", "repeat after me {args}"),
+    ("echo", "say {args}"),
+    ("echo", "repeat {args}"),
+    ("help", "what can you do"),
+    ("help", "show me the commands"),
+    ("help", "how does this work"),
+    ("quit", "shut down"),
+];
+
+// Keyword synonyms per intent. Multi-word phrases are matched as a sequence.
+const SYNONYMS: &[(&str, &[&str])] = &[
+    ("ls", &["ls", "list", "dir", "directory", "folder", "files", "contents", "browse"]),
+    ("create_note", &["create_note", "note", "memo", "jot"]),
+    ("echo", &["echo", "say", "repeat", "print"]),
+    ("ipfs_id", &["ipfs_id", "peer id", "node id", "ipfs id", "identity"]),
+    ("ipfs_add", &["ipfs_add", "upload", "pin", "publish"]),
+    ("ipfs_cat", &["ipfs_cat", "download", "fetch", "retrieve", "cat"]),
+    ("help", &["help", "commands", "usage", "manual"]),
+    ("quit", &["quit", "exit", "bye", "goodbye"]),
+];
+
+// Words that carry no argument meaning when they lead the arguments text.
+const FILLER_WORDS: &[&str] = &["me", "my", "the", "a", "an", "in", "of", "to", "for", "called", "named", "titled", "about", "please"];
+
+pub struct RuleBasedNluBackend;
+
+impl RuleBasedNluBackend {
+    pub fn new() -> Self {
+        RuleBasedNluBackend
+    }
+
+    // Synchronous core of `classify`, kept separate so other modules can reuse it.
+    pub fn classify_text(&self, text: &str) -> NluResponse {
+        let original: Vec<&str> = text.split_whitespace().collect();
+        let mut lowered: Vec<String> = original.iter().map(|w| normalize_word(w)).collect();
+
+        // Politeness prefix does not change the intent.
+        let skip = if lowered.first().map(String::as_str) == Some("please") { 1 } else { 0 };
+        let original = &original[skip..];
+        lowered.drain(..skip);
+
+        if let Some(response) = match_templates(text, original, &lowered) {
+            return response;
+        }
+        if let Some(response) = match_synonyms(text, original, &lowered) {
+            return response;
+        }
+        if let Some(response) = match_fuzzy(text, original, &lowered) {
+            return response;
+        }
+        NluResponse {
+            original_text: text.to_string(),
+            intent: "unknown".to_string(),
+            predicted_label: "no_match".to_string(),
+            confidence: 0.0,
+            arguments_text: String::new(),
+        }
+    }
+}
+
+impl Default for RuleBasedNluBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl NluBackend for RuleBasedNluBackend {
+    fn name(&self) -> &str {
+        "rules"
+    }
+
+    async fn classify(&self, text: &str) -> Result<NluResponse, NluError> {
+        Ok(self.classify_text(text))
+    }
+}
+
+// Lowercases and strips surrounding punctuation ("files?" -> "files").
+fn normalize_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '\'' && c != '.' && c != '/' && c != '~')
+        .trim_end_matches(['.', '\''])
+        .to_lowercase()
+}
+
+fn match_templates(text: &str, original: &[&str], lowered: &[String]) -> Option<NluResponse> {
+    for (intent, template) in TEMPLATES {
+        let tpl: Vec<&str> = template.split_whitespace().collect();
+        let slot = tpl.iter().position(|t| *t == "{args}");
+        let arguments = match slot {
+            None => {
+                if tpl.len() != lowered.len() || !tpl.iter().zip(lowered).all(|(t, w)| t == w) {
+                    continue;
+                }
+                String::new()
+            }
+            Some(k) => {
+                let prefix = &tpl[..k];
+                let suffix = &tpl[k + 1..];
+                if lowered.len() < prefix.len() + suffix.len() + 1 {
+                    continue;
+                }
+                let suffix_start = lowered.len() - suffix.len();
+                let prefix_ok = prefix.iter().zip(lowered).all(|(t, w)| t == w);
+                let suffix_ok = suffix.iter().zip(&lowered[suffix_start..]).all(|(t, w)| t == w);
+                if !prefix_ok || !suffix_ok {
+                    continue;
+                }
+                original[prefix.len()..suffix_start].join(" ")
+            }
+        };
+        return Some(NluResponse {
+            original_text: text.to_string(),
+            intent: intent.to_string(),
+            predicted_label: format!("template: {}", template),
+            confidence: TEMPLATE_CONFIDENCE,
+            arguments_text: arguments,
+        });
+    }
+    None
+}
+
+// Earliest synonym in the utterance wins; ties go to the longest phrase.
+fn match_synonyms(text: &str, original: &[&str], lowered: &[String]) -> Option<NluResponse> {
+    let mut best: Option<(usize, usize, &str, &str)> = None; // (start, len, intent, phrase)
+    for (intent, phrases) in SYNONYMS {
+        for phrase in phrases.iter() {
+            let words: Vec<&str> = phrase.split_whitespace().collect();
+            if words.len() > lowered.len() {
+                continue;
+            }
+            for start in 0..=(lowered.len() - words.len()) {
+                if words.iter().zip(&lowered[start..]).all(|(p, w)| p == w) {
+                    let better = match best {
+                        None => true,
+                        Some((b_start, b_len, _, _)) => start < b_start || (start == b_start && words.len() > b_len),
+                    };
+                    if better {
+                        best = Some((start, words.len(), intent, phrase));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    best.map(|(start, len, intent, phrase)| NluResponse {
+        original_text: text.to_string(),
+        intent: intent.to_string(),
+        predicted_label: format!("keyword: {}", phrase),
+        confidence: SYNONYM_CONFIDENCE,
+        arguments_text: trailing_arguments(original, lowered, start + len),
+    })
+}
+
+// Typo-tolerant match of single-word synonyms ("lsit", "donwload").
+fn match_fuzzy(text: &str, original: &[&str], lowered: &[String]) -> Option<NluResponse> {
+    for (index, word) in lowered.iter().enumerate() {
+        if word.chars().count() < 4 {
+            continue;
+        }
+        let max_distance = if word.chars().count() >= 7 { 2 } else { 1 };
+        let mut best: Option<(usize, &str, &str)> = None;
+        for (intent, phrases) in SYNONYMS {
+            for phrase in phrases.iter().filter(|p| !p.contains(' ') && p.len() >= 4) {
+                let distance = edit_distance(word, phrase);
+                if distance <= max_distance && best.is_none_or(|(d, _, _)| distance < d) {
+                    best = Some((distance, intent, phrase));
+                }
+            }
+        }
+        if let Some((distance, intent, phrase)) = best {
+            return Some(NluResponse {
+                original_text: text.to_string(),
+                intent: intent.to_string(),
+                predicted_label: format!("fuzzy: {} ~ {}", word, phrase),
+                confidence: FUZZY_CONFIDENCE - 0.05 * (distance as f64 - 1.0),
+                arguments_text: trailing_arguments(original, lowered, index + 1),
+            });
+        }
+    }
+    None
+}
+
+fn trailing_arguments(original: &[&str], lowered: &[String], from: usize) -> String {
+    let mut start = from.min(original.len());
+    while start < lowered.len() && FILLER_WORDS.contains(&lowered[start].as_str()) {
+        start += 1;
+    }
+    original[start..].join(" ")
+}
+
+// Edit distance where swapping two adjacent letters counts as one typo
+// (optimal string alignment), so "lsit" is one edit away from "list".
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(text: &str) -> NluResponse {
+        RuleBasedNluBackend::new().classify_text(text)
+    }
+
+    #[test]
+    fn templates_capture_the_argument_slot() {
+        let response = classify("Create a note called Groceries for Sunday");
+        assert_eq!(response.intent, "create_note");
+        assert_eq!(response.arguments_text, "Groceries for Sunday");
+        assert_eq!(response.confidence, TEMPLATE_CONFIDENCE);
+
+        let response = classify("add ./report.pdf to IPFS");
+        assert_eq!(response.intent, "ipfs_add");
+        assert_eq!(response.arguments_text, "./report.pdf");
+    }
+
+    #[test]
+    fn templates_without_a_slot_must_match_the_whole_utterance() {
+        assert_eq!(classify("what can you do?").intent, "help");
+        assert_eq!(classify("please shut down").intent, "quit");
+        assert_ne!(classify("what can you do for me").predicted_label, "template: what can you do");
+    }
+
+    #[test]
+    fn the_earliest_synonym_wins_and_fillers_are_dropped() {
+        let response = classify("show the folder of /var/log");
+        assert_eq!(response.intent, "ls");
+        assert_eq!(response.predicted_label, "keyword: folder");
+        assert_eq!(response.arguments_text, "/var/log");
+        assert_eq!(response.confidence, SYNONYM_CONFIDENCE);
+
+        // "peer id" starts before "upload" and is a two-word phrase.
+        assert_eq!(classify("which peer id did I upload with").intent, "ipfs_id");
+    }
+
+    #[test]
+    fn typos_fall_back_to_fuzzy_matching() {
+        let response = classify("lsit ~/Documents");
+        assert_eq!(response.intent, "ls");
+        assert_eq!(response.arguments_text, "~/Documents");
+        assert_eq!(response.confidence, FUZZY_CONFIDENCE);
+
+        assert_eq!(classify("donwload QmXyz").intent, "ipfs_cat");
+        // Short words are never fuzzy-matched.
+        assert_eq!(classify("lis").intent, "unknown");
+    }
+
+    #[test]
+    fn unmatched_text_is_unknown_with_zero_confidence() {
+        let response = classify("the weather is lovely today");
+        assert_eq!(response.intent, "unknown");
+        assert_eq!(response.confidence, 0.0);
+        assert!(response.arguments_text.is_empty());
+    }
+
+    #[test]
+    fn edit_distance_counts_a_transposition_as_one_edit() {
+        assert_eq!(edit_distance("lsit", "list"), 1);
+        assert_eq!(edit_distance("donwload", "download"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "note"), 4);
+    }
+}
@@ -0,0 +1,123 @@
+// src/openapi.rs for omnimind-core
+// The OpenAPI 3 document served at /openapi.json. Routes and request/response
+// types come from the utoipa annotations on the handlers; the per-command
+// argument schemas are generated from the command registry.
+use actix_web::HttpResponse;
+use utoipa::openapi::extensions::ExtensionsBuilder;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, SchemaFormat, Type};
+use utoipa::openapi::{OpenApi as OpenApiDocument, RefOr};
+use utoipa::OpenApi;
+
+use crate::api::{self, CreateNoteRequest, UploadForm};
+use crate::registry::{self, CommandSpec};
+use crate::response::{CommandResponse, CommandStatus, ErrorCode};
+use crate::siwe::{self, SiweVerifyRequest};
+use crate::CommandRequest;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "OmniMind Core API",
+        description = "Text commands (POST /command) and typed REST endpoints sharing the same command implementations. Every response uses the CommandResponse envelope.",
+    ),
+    paths(
+        crate::handle_command_request,
+        api::list_notes,
+        api::create_note,
+        api::get_note,
+        api::list_fs,
+        api::ipfs_add,
+        api::ipfs_cat,
+        crate::events::events_stream,
+        crate::metrics::metrics_endpoint,
+        crate::health::healthz,
+        crate::health::readyz,
+        siwe::nonce,
+        siwe::verify_signature,
+        siwe::me,
+    ),
+    components(schemas(CommandRequest, CommandResponse, CommandStatus, ErrorCode, CreateNoteRequest, UploadForm, SiweVerifyRequest)),
+    tags(
+        (name = "commands", description = "Free-form command lines and natural-language requests"),
+        (name = "notes", description = "Notes in the notes directory (storage.notes_dir)"),
+        (name = "filesystem", description = "Local directory listings"),
+        (name = "ipfs", description = "The local IPFS node"),
+        (name = "events", description = "Live server-sent events of core activity"),
+        (name = "monitoring", description = "Health and readiness probes and Prometheus metrics"),
+        (name = "auth", description = "Sign-In with Ethereum (EIP-4361) and the current caller"),
+    )
+)]
+struct ApiDoc;
+
+// The full document: annotated routes plus a CommandName enum and one
+// "<Command>Arguments" schema per registered command.
+pub fn document() -> OpenApiDocument {
+    let mut doc = ApiDoc::openapi();
+    let components = doc.components.get_or_insert_with(Default::default);
+
+    let names: Vec<&str> = registry::COMMANDS.iter().map(|spec| spec.name).collect();
+    let command_name = ObjectBuilder::new()
+        .schema_type(Type::String)
+        .description(Some("First word of raw_command for a direct (non-NLU) command."))
+        .enum_values(Some(names))
+        .build();
+    components.schemas.insert("CommandName".to_string(), RefOr::T(Schema::Object(command_name)));
+
+    for spec in registry::COMMANDS {
+        components.schemas.insert(arguments_schema_name(spec.name), RefOr::T(Schema::Object(arguments_schema(spec))));
+    }
+    doc
+}
+
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(document())
+}
+
+// "create_note" -> "CreateNoteArguments"
+fn arguments_schema_name(command: &str) -> String {
+    let mut name: String = command
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect();
+    name.push_str("Arguments");
+    name
+}
+
+// Arguments are typed positionally after the command name; properties are listed
+// in that order and x-omnimind-command records the name and usage line.
+fn arguments_schema(spec: &CommandSpec) -> utoipa::openapi::schema::Object {
+    let mut schema = ObjectBuilder::new()
+        .schema_type(Type::Object)
+        .description(Some(format!("{} Usage: {}", spec.summary, spec.usage)))
+        .extensions(Some(
+            ExtensionsBuilder::new()
+                .add("x-omnimind-command", serde_json::json!({
+                    "name": spec.name,
+                    "usage": spec.usage,
+                    "argument_order": spec.args.iter().map(|arg| arg.name).collect::<Vec<_>>(),
+                    "produces": spec.produces,
+                    "nlu_intent": spec.nlu_intent,
+                    "scope": spec.scope,
+                }))
+                .build(),
+        ));
+
+    for arg in spec.args {
+        let description = if arg.rest { format!("{} Takes the rest of the command line.", arg.description) } else { arg.description.to_string() };
+        let mut property = ObjectBuilder::new()
+            .schema_type(Type::String)
+            .description(Some(description))
+            .format(arg.format.map(|format| SchemaFormat::Custom(format.to_string())));
+        if !arg.choices.is_empty() {
+            property = property.enum_values(Some(arg.choices.to_vec()));
+        }
+        schema = schema.property(arg.name, property);
+        if arg.required {
+            schema = schema.required(arg.name);
+        }
+    }
+    schema.build()
+}
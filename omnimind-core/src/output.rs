@@ -0,0 +1,108 @@
+// src/output.rs for omnimind-core
+// Human-readable rendering for the terminal modes. A CommandResponse becomes its
+// message with a status marker, then `data` as tables: lists of records get a
+// column per field, flat objects a field/value table.
+use comfy_table::presets::UTF8_FULL_CONDENSED;
+use comfy_table::{ContentArrangement, Table};
+use serde_json::{Map, Value};
+
+use crate::progress::ProgressEvent;
+use crate::response::{CommandResponse, CommandStatus, ErrorCode};
+
+pub fn render(response: &CommandResponse) -> String {
+    let mut text = match (response.status, response.code) {
+        (CommandStatus::Error, Some(code)) => format!("❌ {} [{}]", response.message, code_name(code)),
+        (CommandStatus::Error, None) => format!("❌ {}", response.message),
+        (CommandStatus::Info, _) => format!("ℹ️  {}", response.message),
+        (CommandStatus::Success, _) => response.message.clone(),
+    };
+    if let Some(data) = &response.data {
+        let data = render_value(data);
+        if !data.is_empty() {
+            text.push('\n');
+            text.push_str(&data);
+        }
+    }
+    text
+}
+
+// One line for a progress report, indented under the command it belongs to.
+pub fn render_progress(event: &ProgressEvent) -> String {
+    match event {
+        ProgressEvent::Progress { message, done: Some(done), total: Some(total), .. } => format!("  … {} ({}/{} bytes)", message, done, total),
+        ProgressEvent::Progress { message, .. } => format!("  … {}", message),
+        ProgressEvent::Output { message, .. } => format!("  {}", message),
+    }
+}
+
+// The snake_case name clients see in JSON, e.g. "path_not_found".
+fn code_name(code: ErrorCode) -> String {
+    serde_json::to_value(code).ok().and_then(|code| code.as_str().map(String::from)).unwrap_or_default()
+}
+
+fn render_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => records_table(items).to_string(),
+        Value::Array(items) => items.iter().map(cell).collect::<Vec<_>>().join("\n"),
+        Value::Object(fields) => render_object(fields),
+        other => cell(other),
+    }
+}
+
+// Scalar fields go in one field/value table; each list of records gets its own
+// table underneath, headed by its field name (e.g. the steps of a plan).
+fn render_object(fields: &Map<String, Value>) -> String {
+    let mut sections = Vec::new();
+    let mut scalars = new_table();
+    scalars.set_header(vec!["field", "value"]);
+    let mut has_scalars = false;
+    for (name, value) in fields {
+        match value {
+            Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_object) => {
+                sections.push(format!("{}:\n{}", name, records_table(items)));
+            }
+            _ => {
+                scalars.add_row(vec![name.clone(), cell(value)]);
+                has_scalars = true;
+            }
+        }
+    }
+    if has_scalars {
+        sections.insert(0, scalars.to_string());
+    }
+    sections.join("\n")
+}
+
+fn records_table(records: &[Value]) -> Table {
+    // Columns in first-seen order, so records with extra fields still fit.
+    let mut columns: Vec<&String> = Vec::new();
+    for record in records {
+        for name in record.as_object().into_iter().flat_map(Map::keys) {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+    let mut table = new_table();
+    table.set_header(columns.iter().map(|name| name.as_str()));
+    for record in records {
+        table.add_row(columns.iter().map(|name| record.get(name.as_str()).map(cell).unwrap_or_default()));
+    }
+    table
+}
+
+fn new_table() -> Table {
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL_CONDENSED).set_content_arrangement(ContentArrangement::Dynamic);
+    table
+}
+
+// Strings without quotes, nested values as compact JSON.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,282 @@
+// src/planner.rs for omnimind-core
+// Multi-step command plans: split a compound request ("create a note called todo
+// and add it to IPFS") into ordered registry commands, with outputs of earlier
+// steps (the created path) feeding the arguments of later ones.
+use serde::Serialize;
+
+use crate::nlu::corrections::CorrectionStore;
+use crate::nlu::{NluBackend, NluError, NluResponse, RuleBasedNluBackend};
+use crate::registry;
+
+// Words that join two requests when followed by an action.
+const CONNECTORS: &[&str] = &["and", "then", "also"];
+
+// First words that signal the start of a new step after a connector.
+const ACTION_WORDS: &[&str] = &[
+    "add", "upload", "pin", "put", "share", "publish", "list", "show", "display", "create", "make", "write",
+    "new", "note", "get", "download", "fetch", "read", "retrieve", "say", "repeat", "print", "tell",
+];
+
+// Arguments that point back at the previous step's output.
+const REFERENCE_PHRASES: &[&str] = &[
+    "it", "that", "this", "them", "the note", "that note", "this note", "the file", "that file",
+    "this file", "the result", "the cid", "the path", "the new note",
+];
+
+// Commands that cannot run without an argument; an empty argument after a
+// producing step is taken as a reference to it.
+const NEEDS_ARGUMENT: &[&str] = &["create_note", "ipfs_add", "ipfs_cat"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepArgument {
+    Literal { text: String },
+    FromStep { step: usize, field: String }, // 1-based step number
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanStep {
+    pub step: usize, // 1-based
+    pub text: String, // The part of the request this step came from
+    pub command: String,
+    pub argument: StepArgument,
+    pub resolved_by: String,
+    pub confidence: f64,
+}
+
+impl PlanStep {
+    // Human-readable command line, with references shown as <field of step N>.
+    pub fn preview(&self) -> String {
+        match &self.argument {
+            StepArgument::Literal { text } if text.is_empty() => self.command.clone(),
+            StepArgument::Literal { text } => format!("{} {}", self.command, text),
+            StepArgument::FromStep { step, field } => format!("{} <{} from step {}>", self.command, field, step),
+        }
+    }
+
+    // Arguments with references filled in from earlier step outputs.
+    pub fn arguments_with(&self, outputs: &[Option<serde_json::Value>]) -> Result<String, PlanError> {
+        match &self.argument {
+            StepArgument::Literal { text } => Ok(text.clone()),
+            StepArgument::FromStep { step, field } => {
+                let value = outputs.get(step - 1).and_then(|data| data.as_ref()).and_then(|data| data.get(field));
+                match value {
+                    Some(serde_json::Value::String(text)) => Ok(text.clone()),
+                    Some(other) => Ok(other.to_string()),
+                    None => Err(PlanError::MissingOutput { step: *step, field: field.clone(), consumer: self.step }),
+                }
+            }
+        }
+    }
+}
+
+// PlanError: Why a request could not be turned into a plan
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PlanError {
+    #[error("Nothing to plan: the request is empty.")]
+    Empty,
+    #[error("Could not understand the step '{0}'.")]
+    Unresolved(String), // The step text no resolver understood
+    #[error("Step {step} did not produce a '{field}' for step {consumer}.")]
+    MissingOutput { step: usize, field: String, consumer: usize },
+    #[error(transparent)]
+    Nlu(#[from] NluError),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Plan {
+    pub request: String,
+    pub steps: Vec<PlanStep>,
+}
+
+// Splits `request` at connectors that are followed by an action word. Titles
+// like "salt and pepper" stay intact because "pepper" is not an action.
+pub fn split_request(request: &str) -> Vec<String> {
+    let words: Vec<&str> = request.split_whitespace().collect();
+    let mut segments = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        if let Some(stripped) = word.strip_suffix(';') {
+            if !stripped.is_empty() {
+                current.push(stripped);
+            }
+            push_segment(&mut segments, &mut current);
+            i += 1;
+            continue;
+        }
+
+        // Skip over a run of connectors ("and then") and check what follows.
+        let mut next = i;
+        while next < words.len() && CONNECTORS.contains(&clean_word(words[next]).as_str()) {
+            next += 1;
+        }
+        let starts_action = next > i && next < words.len() && is_action_word(&clean_word(words[next]));
+        if starts_action && !current.is_empty() {
+            if let Some(last) = current.last_mut() {
+                *last = last.trim_end_matches(',');
+            }
+            push_segment(&mut segments, &mut current);
+            i = next;
+            continue;
+        }
+        current.push(word);
+        i += 1;
+    }
+    push_segment(&mut segments, &mut current);
+    segments
+}
+
+fn push_segment(segments: &mut Vec<String>, current: &mut Vec<&str>) {
+    if !current.is_empty() {
+        segments.push(current.join(" "));
+        current.clear();
+    }
+}
+
+fn clean_word(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_').to_lowercase()
+}
+
+fn is_action_word(word: &str) -> bool {
+    ACTION_WORDS.contains(&word) || registry::is_nlu_intent(word)
+}
+
+// Resolves every segment of `request` to a registry command. Order per segment:
+// literal command keyword, taught phrase, built-in template match (best at
+// extracting arguments), configured NLU backend, any built-in rule match.
+pub async fn build_plan(
+    request: &str,
+    corrections: &CorrectionStore,
+    nlu: Option<&dyn NluBackend>,
+    confidence_threshold: f64,
+) -> Result<Plan, PlanError> {
+    let rules = RuleBasedNluBackend::new();
+    let mut steps: Vec<PlanStep> = Vec::new();
+
+    for (index, segment) in split_request(request).into_iter().enumerate() {
+        let nlu_result = resolve_segment(&segment, corrections, nlu, &rules, confidence_threshold).await?;
+        let argument = link_argument(&nlu_result, &steps);
+        steps.push(PlanStep {
+            step: index + 1,
+            text: segment,
+            command: nlu_result.intent,
+            argument,
+            resolved_by: nlu_result.predicted_label,
+            confidence: nlu_result.confidence,
+        });
+    }
+
+    if steps.is_empty() {
+        return Err(PlanError::Empty);
+    }
+    Ok(Plan { request: request.trim().to_string(), steps })
+}
+
+async fn resolve_segment(
+    segment: &str,
+    corrections: &CorrectionStore,
+    nlu: Option<&dyn NluBackend>,
+    rules: &RuleBasedNluBackend,
+    confidence_threshold: f64,
+) -> Result<NluResponse, PlanError> {
+    let mut words = segment.split_whitespace();
+    let first = words.next().unwrap_or("").to_lowercase();
+    if registry::is_nlu_intent(&first) {
+        return Ok(NluResponse {
+            original_text: segment.to_string(),
+            intent: first,
+            predicted_label: "keyword".to_string(),
+            confidence: 1.0,
+            arguments_text: words.collect::<Vec<_>>().join(" "),
+        });
+    }
+    if let Some(taught) = corrections.lookup(segment) {
+        return Ok(taught);
+    }
+
+    let rule_result = rules.classify_text(segment);
+    if rule_result.predicted_label.starts_with("template:") {
+        return Ok(rule_result);
+    }
+    if let Some(backend) = nlu {
+        let nlu_result = backend.classify(segment).await?;
+        if nlu_result.confidence >= confidence_threshold && registry::is_nlu_intent(&nlu_result.intent) {
+            return Ok(nlu_result);
+        }
+    }
+    if rule_result.confidence >= confidence_threshold {
+        return Ok(rule_result);
+    }
+    Err(PlanError::Unresolved(segment.to_string()))
+}
+
+// Turns "it"/"the note" (or a missing required argument) into a reference to
+// the closest earlier step that produces something.
+fn link_argument(nlu_result: &NluResponse, earlier: &[PlanStep]) -> StepArgument {
+    let text = nlu_result.arguments_text.trim();
+    let lowered = text.to_lowercase();
+    let is_reference = REFERENCE_PHRASES.contains(&lowered.as_str())
+        || (text.is_empty() && NEEDS_ARGUMENT.contains(&nlu_result.intent.as_str()));
+
+    if is_reference {
+        let producer = earlier.iter().rev().find_map(|step| {
+            registry::find(&step.command).and_then(|spec| spec.produces).map(|field| (step.step, field))
+        });
+        if let Some((step, field)) = producer {
+            return StepArgument::FromStep { step, field: field.to_string() };
+        }
+    }
+    StepArgument::Literal { text: text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_connectors_followed_by_an_action() {
+        assert_eq!(
+            split_request("create a note called todo and then add it to IPFS"),
+            vec!["create a note called todo", "add it to IPFS"]
+        );
+        assert_eq!(split_request("list files in /tmp, and say done"), vec!["list files in /tmp", "say done"]);
+        assert_eq!(split_request("echo one; echo two"), vec!["echo one", "echo two"]);
+    }
+
+    #[test]
+    fn keeps_connectors_that_do_not_start_an_action() {
+        assert_eq!(split_request("create a note called salt and pepper"), vec!["create a note called salt and pepper"]);
+        assert_eq!(split_request("say this and that"), vec!["say this and that"]);
+    }
+
+    #[test]
+    fn ignores_empty_segments() {
+        assert!(split_request("   ").is_empty());
+        assert_eq!(split_request("; echo hi ;"), vec!["echo hi"]);
+    }
+
+    #[tokio::test]
+    async fn references_link_to_the_producing_step() {
+        let corrections = CorrectionStore::load(std::env::temp_dir().join(format!("omnimind-planner-test-{}.json", uuid::Uuid::new_v4())));
+        let plan = build_plan("create a note called todo and add it to ipfs", &corrections, None, 0.5).await.unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.steps[0].command, "create_note");
+        assert_eq!(plan.steps[0].preview(), "create_note todo");
+        assert_eq!(plan.steps[1].command, "ipfs_add");
+        assert_eq!(plan.steps[1].preview(), "ipfs_add <path from step 1>");
+
+        let outputs = vec![Some(serde_json::json!({ "path": "omni_notes/todo.txt" }))];
+        assert_eq!(plan.steps[1].arguments_with(&outputs).unwrap(), "omni_notes/todo.txt");
+        assert!(matches!(plan.steps[1].arguments_with(&[None]), Err(PlanError::MissingOutput { step: 1, .. })));
+    }
+
+    #[tokio::test]
+    async fn unresolved_steps_fail_the_plan() {
+        let corrections = CorrectionStore::load(std::env::temp_dir().join(format!("omnimind-planner-test-{}.json", uuid::Uuid::new_v4())));
+        let result = build_plan("echo hi and then make the weather nicer", &corrections, None, 0.5).await;
+        assert!(matches!(result, Err(PlanError::Unresolved(step)) if step == "make the weather nicer"));
+        assert!(matches!(build_plan(" ", &corrections, None, 0.5).await, Err(PlanError::Empty)));
+    }
+}
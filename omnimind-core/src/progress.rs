@@ -0,0 +1,60 @@
+// src/progress.rs for omnimind-core
+// Progress reporting for long-running commands. Commands report stages, byte
+// counts and partial output to a Progress handle; over /ws these are forwarded to
+// the client as they happen, over plain HTTP the handle is a no-op.
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+// ProgressEvent: Something a command reports before its final result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    Progress {
+        stage: String, // e.g. "nlu", "ipfs_add"
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        done: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<u64>,
+    },
+    Output {
+        message: String, // Partial result, e.g. one finished plan step
+        data: Option<serde_json::Value>,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    sender: Option<UnboundedSender<ProgressEvent>>,
+}
+
+impl Progress {
+    // Discards every report; used where nobody is listening.
+    pub fn none() -> Self {
+        Progress::default()
+    }
+
+    pub fn channel() -> (Self, UnboundedReceiver<ProgressEvent>) {
+        let (sender, receiver) = unbounded_channel();
+        (Progress { sender: Some(sender) }, receiver)
+    }
+
+    pub fn stage(&self, stage: &str, message: impl Into<String>) {
+        self.send(ProgressEvent::Progress { stage: stage.to_string(), message: message.into(), done: None, total: None });
+    }
+
+    pub fn bytes(&self, stage: &str, message: impl Into<String>, done: u64, total: u64) {
+        self.send(ProgressEvent::Progress { stage: stage.to_string(), message: message.into(), done: Some(done), total: Some(total) });
+    }
+
+    pub fn output(&self, message: impl Into<String>, data: Option<serde_json::Value>) {
+        self.send(ProgressEvent::Output { message: message.into(), data });
+    }
+
+    fn send(&self, event: ProgressEvent) {
+        if let Some(sender) = &self.sender {
+            // The listener may have gone away (client disconnected); the command still finishes.
+            let _ = sender.send(event);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// src/registry.rs for omnimind-core
+// The command registry: every command the core understands, its usage and what
+// it hands on to later steps of a plan. Help text is generated from this table.
+// User-defined aliases from the config are layered on top.
+use crate::config;
+
+// ArgSpec: One positional argument of a command, in the order it is typed
+#[derive(Debug, Clone, Copy)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+    pub format: Option<&'static str>, // OpenAPI string format hint, e.g. "path" or "cid"
+    pub choices: &'static [&'static str], // Allowed values, empty for free text
+    pub rest: bool, // Takes the remainder of the line (titles, phrases)
+}
+
+impl ArgSpec {
+    const fn new(name: &'static str, description: &'static str, required: bool) -> Self {
+        ArgSpec { name, description, required, format: None, choices: &[], rest: false }
+    }
+
+    const fn format(self, format: &'static str) -> Self {
+        ArgSpec { format: Some(format), ..self }
+    }
+
+    const fn choices(self, choices: &'static [&'static str]) -> Self {
+        ArgSpec { choices, ..self }
+    }
+
+    const fn rest(self) -> Self {
+        ArgSpec { rest: true, ..self }
+    }
+}
+
+// CommandSpec: One registered command
+#[derive(Debug, Clone, Copy)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub summary: &'static str,
+    pub args: &'static [ArgSpec],
+    // Field of the response `data` that later plan steps can consume (e.g. "path").
+    pub produces: Option<&'static str>,
+    // Whether NLU results and multi-step plans may resolve to this command.
+    pub nlu_intent: bool,
+    // API token scope needed to run it (see auth.rs); None for harmless commands.
+    pub scope: Option<&'static str>,
+}
+
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "echo", usage: "echo <text>", summary: "Prints back the text you provide.",
+        args: &[ArgSpec::new("text", "Text to print back.", false).rest()],
+        produces: None, nlu_intent: true, scope: None,
+    },
+    CommandSpec {
+        name: "ls", usage: "ls [path]", summary: "Lists files and directories.",
+        args: &[ArgSpec::new("path", "Directory to list (default: current directory).", false).format("path")],
+        produces: None, nlu_intent: true, scope: Some("fs:read"),
+    },
+    CommandSpec {
+        name: "create_note", usage: "create_note <title>", summary: "Creates a new text note in the notes directory.",
+        args: &[ArgSpec::new("title", "Note title; also used (sanitized) as the file name.", true).rest()],
+        produces: Some("path"), nlu_intent: true, scope: Some("notes:write"),
+    },
+    CommandSpec {
+        name: "ipfs_id", usage: "ipfs_id", summary: "Fetches the ID of the local IPFS node.",
+        args: &[],
+        produces: Some("ipfsNodeId"), nlu_intent: true, scope: Some("ipfs:read"),
+    },
+    CommandSpec {
+        name: "ipfs_add", usage: "ipfs_add <file_path>", summary: "Adds a local file to IPFS and returns its CID.",
+        args: &[ArgSpec::new("file_path", "Local file to add.", true).format("path").rest()],
+        produces: Some("cid"), nlu_intent: true, scope: Some("ipfs:add"),
+    },
+    CommandSpec {
+        name: "ipfs_cat", usage: "ipfs_cat <cid>", summary: "Retrieves and displays content from IPFS for a given CID.",
+        args: &[ArgSpec::new("cid", "Content identifier (Qm... or ba...).", true).format("cid")],
+        produces: Some("content"), nlu_intent: true, scope: Some("ipfs:read"),
+    },
+    CommandSpec {
+        name: "plan", usage: "plan <request>", summary: "Previews the steps of a multi-step request without running them.",
+        args: &[ArgSpec::new("request", "Natural-language request, e.g. \"create a note called todo and add it to IPFS\".", true).rest()],
+        produces: None, nlu_intent: false, scope: None,
+    },
+    CommandSpec {
+        name: "teach", usage: "teach \"<phrase>\" as <command>", summary: "Teaches OmniMind what a phrase means.",
+        args: &[
+            ArgSpec::new("phrase", "The phrase to learn, in quotes.", true),
+            ArgSpec::new("command", "The command line it stands for, after the word 'as'.", true).rest(),
+        ],
+        produces: None, nlu_intent: false, scope: Some("nlu:admin"),
+    },
+    CommandSpec {
+        name: "corrections", usage: "corrections [list|export|forget]", summary: "Lists, exports (fewshot|jsonl) or forgets taught phrases.",
+        args: &[
+            ArgSpec::new("action", "What to do (default: list).", false).choices(&["list", "export", "forget"]),
+            ArgSpec::new("argument", "Export format (fewshot or jsonl) or the phrase to forget.", false).rest(),
+        ],
+        produces: None, nlu_intent: false, scope: Some("nlu:admin"),
+    },
+    CommandSpec {
+        name: "nlu_cache", usage: "nlu_cache [clear]", summary: "Shows NLU cache hit/miss statistics, or clears the cache.",
+        args: &[ArgSpec::new("action", "Pass 'clear' to empty the cache.", false).choices(&["clear"])],
+        produces: None, nlu_intent: false, scope: Some("nlu:admin"),
+    },
+    CommandSpec {
+        name: "history", usage: "history [count|search <text>]", summary: "Shows the most recently executed commands, or searches all of them.",
+        args: &[
+            ArgSpec::new("count", "How many commands to show (default 20), or 'search'.", false),
+            ArgSpec::new("text", "With search: text to find in the command, what it ran, the caller or the result.", false).rest(),
+        ],
+        produces: None, nlu_intent: false, scope: Some("history:read"),
+    },
+    CommandSpec {
+        name: "verify_audit", usage: "verify_audit", summary: "Checks the history's hash chain and IPFS anchors for tampering.",
+        args: &[],
+        produces: None, nlu_intent: false, scope: Some("history:read"),
+    },
+    CommandSpec { name: "help", usage: "help", summary: "Shows this help message.", args: &[], produces: None, nlu_intent: true, scope: None },
+    CommandSpec { name: "quit", usage: "quit", summary: "Explains how to stop the server.", args: &[], produces: None, nlu_intent: true, scope: None },
+];
+
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+// True if NLU and plans may dispatch to `name`.
+pub fn is_nlu_intent(name: &str) -> bool {
+    find(name).is_some_and(|spec| spec.nlu_intent)
+}
+
+pub fn nlu_intent_names() -> Vec<&'static str> {
+    COMMANDS.iter().filter(|spec| spec.nlu_intent).map(|spec| spec.name).collect()
+}
+
+pub fn help_message() -> String {
+    let mut help = String::from("Available commands:");
+    for spec in COMMANDS.iter().filter(|spec| spec.name != "quit") {
+        help.push_str(&format!("\n  {:<20} - {}", spec.usage, spec.summary));
+    }
+    let aliases = &config::current().aliases;
+    if !aliases.is_empty() {
+        help.push_str("\nAliases:");
+        for (name, command) in aliases.iter() {
+            help.push_str(&format!("\n  {:<20} - {}", name, command));
+        }
+    }
+    help
+}
+
+// --- Aliases ---
+
+// Expands a command line starting with an alias from the [aliases] config
+// section: `ll docs` with ll = "ls omni_notes" becomes "ls omni_notes docs".
+// Aliases are read on every call, so config reloads apply immediately.
+pub fn expand_alias(command_line: &str) -> Option<String> {
+    let trimmed = command_line.trim_start();
+    let keyword = trimmed.split_whitespace().next()?;
+    let config = config::current();
+    let (_, command) = config.aliases.iter().find(|(name, _)| name.eq_ignore_ascii_case(keyword))?;
+    Some(format!("{} {}", command, trimmed[keyword.len()..].trim()).trim_end().to_string())
+}
@@ -0,0 +1,196 @@
+// src/reload.rs for omnimind-core
+// Hot reload: polls the config file and applies edits while the server runs.
+// Most settings (thresholds, CORS origins, IPFS endpoint, notes directory,
+// aliases, SIWE domains, wallet scopes, limits and the log level) are read from config::current() on
+// each use and apply at once. The NLU backend is rebuilt only when its own
+// settings changed, since the Python model takes a long time to load. Listener
+// and storage file settings need a restart and keep their startup values.
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::web;
+
+use crate::config::{self, Config};
+use crate::error::OmniError;
+use crate::events::{self, CoreEvent};
+use crate::logging;
+use crate::metrics;
+use crate::nlu;
+use crate::AppState;
+
+// How often the config file is checked for edits.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Keys that only take effect at startup.
+const RESTART_KEYS: &[&str] = &["server.bind", "server.unix_socket", "server.tls_cert", "server.tls_key", "storage.corrections_file", "storage.tokens_file", "history.file", "log.format"];
+// NLU keys that are read on each request and need no new backend.
+const LIVE_NLU_KEYS: &[&str] = &["nlu.confidence_threshold"];
+
+// Why the config file was last rejected, until it validates again (see /readyz).
+static RELOAD_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn reload_error() -> Option<String> {
+    RELOAD_ERROR.lock().ok().and_then(|error| error.clone())
+}
+
+// Reloads whenever the config file's contents change, including when it is
+// created or deleted. `file` and `overrides` are the command-line flags, which
+// keep overriding the file after a reload.
+pub async fn watch_config(app_state: web::Data<AppState>, file: Option<PathBuf>, overrides: Vec<(String, String)>) {
+    let path = config::file_to_watch(file.as_deref());
+    let mut last_contents = std::fs::read(&path).ok();
+    let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let contents = std::fs::read(&path).ok();
+        if contents == last_contents {
+            continue;
+        }
+        last_contents = contents;
+        match reload(&app_state, config::load(file.as_deref(), &overrides)) {
+            Ok(changed) if changed.is_empty() => {}
+            Ok(changed) => tracing::info!("Reloaded configuration from '{}': {} changed.", path.display(), changed.join(", ")),
+            Err(e) => tracing::warn!("Config reload failed, keeping the current configuration: {}", e),
+        }
+    }
+}
+
+// Installs a freshly loaded configuration and returns the keys that changed. A
+// load error is kept for /readyz and leaves everything as it was.
+fn reload(app_state: &AppState, loaded: Result<(Config, config::Sources), OmniError>) -> Result<Vec<String>, OmniError> {
+    let result = loaded.map(|(new, _)| apply(app_state, new));
+    if let Ok(mut error) = RELOAD_ERROR.lock() {
+        *error = result.as_ref().err().map(ToString::to_string);
+    }
+    result
+}
+
+// Installs `new` over the current configuration, keeping the startup values of
+// RESTART_KEYS, and rebuilds the NLU backend if its settings changed.
+fn apply(app_state: &AppState, mut new: Config) -> Vec<String> {
+    let old = config::current();
+    let changed = config::changed_keys(&old, &new);
+    if changed.is_empty() {
+        return changed;
+    }
+
+    let needs_restart: Vec<&str> = changed.iter().map(String::as_str).filter(|key| RESTART_KEYS.contains(key)).collect();
+    if !needs_restart.is_empty() {
+        tracing::warn!("{} changed; restart the server to apply.", needs_restart.join(", "));
+        keep_startup_settings(&mut new, &old);
+    }
+    let rebuild_nlu = changed.iter().any(|key| key.starts_with("nlu.") && !LIVE_NLU_KEYS.contains(&key.as_str()));
+    let nlu_config = new.nlu.clone();
+    if changed.iter().any(|key| key == "log.level") {
+        // Validated by config::load, so this only fails if logging was never set up.
+        if let Err(e) = logging::set_level(&new.log.level) {
+            tracing::warn!("Could not apply log.level: {}", e);
+        }
+    }
+    config::install(new);
+
+    if rebuild_nlu {
+        let backend: Option<Arc<dyn nlu::NluBackend>> = nlu::build_backend(&nlu::NluSettings::from_config(&nlu_config)).map(Arc::from);
+        let name = backend.as_ref().map_or("none".to_string(), |backend| backend.name().to_string());
+        let previous = app_state.replace_nlu(backend);
+        tracing::info!("NLU backend: {} (reloaded)", name);
+        metrics::record_nlu_restart(&name, "reconfigured");
+        events::publish(CoreEvent::NluBackendRestarted { backend: name, reason: "NLU configuration changed".to_string() });
+        // A Python process shuts down in the background once no request holds it.
+        drop(previous);
+    }
+    events::publish(CoreEvent::ConfigReloaded { changed: changed.clone() });
+    changed
+}
+
+fn keep_startup_settings(new: &mut Config, old: &Config) {
+    new.server.bind = old.server.bind.clone();
+    new.server.unix_socket = old.server.unix_socket.clone();
+    new.server.tls_cert = old.server.tls_cert.clone();
+    new.server.tls_key = old.server.tls_key.clone();
+    new.storage.corrections_file = old.storage.corrections_file.clone();
+    new.storage.tokens_file = old.storage.tokens_file.clone();
+    new.history.file = old.history.file.clone();
+    new.log.format = old.log.format.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::testing::ConfigGuard;
+
+    // Installs a config using the rules backend, with state kept in `dir`, and app state built from it.
+    async fn start(dir: &TempDir) -> (AppState, Config, ConfigGuard) {
+        let mut config = Config::default();
+        config.nlu.backend = "rules".to_string();
+        config.storage.tokens_file = dir.path().join("tokens.json");
+        config.storage.corrections_file = dir.path().join("corrections.json");
+        config.history.enabled = false;
+        let guard = config::testing::install(config.clone()).await;
+        (crate::build_app_state(&config).unwrap(), config, guard)
+    }
+
+    fn same_backend(a: &Option<Arc<dyn nlu::NluBackend>>, b: &Option<Arc<dyn nlu::NluBackend>>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_keys_keep_their_startup_values() {
+        let dir = TempDir::new().unwrap();
+        let (app_state, started, _config) = start(&dir).await;
+        let mut edited = started.clone();
+        edited.server.bind = "0.0.0.0:4040".to_string();
+        edited.storage.tokens_file = dir.path().join("other-tokens.json");
+        edited.history.file = dir.path().join("other-history.jsonl");
+        edited.limits.rate_limit_burst = 5;
+
+        let changed = apply(&app_state, edited);
+        assert_eq!(changed, ["history.file", "limits.rate_limit_burst", "server.bind", "storage.tokens_file"]);
+        let current = config::current();
+        assert_eq!(current.server.bind, started.server.bind);
+        assert_eq!(current.storage.tokens_file, started.storage.tokens_file);
+        assert_eq!(current.history.file, started.history.file);
+        assert_eq!(current.limits.rate_limit_burst, 5);
+    }
+
+    #[tokio::test]
+    async fn only_nlu_settings_read_per_request_keep_the_backend() {
+        let dir = TempDir::new().unwrap();
+        let (app_state, started, _config) = start(&dir).await;
+        let backend = app_state.nlu();
+
+        let mut edited = started.clone();
+        edited.nlu.confidence_threshold = 0.9;
+        assert_eq!(apply(&app_state, edited.clone()), ["nlu.confidence_threshold"]);
+        assert!(same_backend(&backend, &app_state.nlu()));
+        assert_eq!(config::current().nlu.confidence_threshold, 0.9);
+
+        edited.nlu.retries = 3;
+        apply(&app_state, edited);
+        assert!(!same_backend(&backend, &app_state.nlu()));
+    }
+
+    #[tokio::test]
+    async fn a_bad_file_is_reported_and_the_running_config_kept() {
+        let dir = TempDir::new().unwrap();
+        let (app_state, started, _config) = start(&dir).await;
+        let file = dir.path().join("omnimind.toml");
+
+        std::fs::write(&file, "[nlu]\nconfidence_threshold = 2.0\n").unwrap();
+        assert!(reload(&app_state, config::load(Some(&file), &[])).is_err());
+        assert!(reload_error().unwrap().contains("nlu.confidence_threshold"));
+        assert_eq!(*config::current(), started);
+
+        // Fixing the file clears the error.
+        let fixed = Config { nlu: config::NluConfig { confidence_threshold: 0.7, ..started.nlu.clone() }, ..started.clone() };
+        std::fs::write(&file, toml::to_string(&fixed).unwrap()).unwrap();
+        let loaded = config::load(Some(&file), &[]);
+        assert_eq!(reload(&app_state, loaded).unwrap(), ["nlu.confidence_threshold"]);
+        assert_eq!(reload_error(), None);
+    }
+}
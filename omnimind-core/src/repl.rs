@@ -0,0 +1,172 @@
+// src/repl.rs for omnimind-core
+// `omnimind-core repl`: an interactive shell over the same dispatcher the API
+// uses (process_omni_command), so commands, NLU, clarifications and plans behave
+// exactly as over HTTP. Line editing and history come from rustyline; tab
+// completes command names, aliases, argument choices, paths and note titles.
+// `client repl` runs the same shell against a running server.
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::client::Target;
+use crate::commands;
+use crate::config;
+use crate::output;
+use crate::registry;
+use crate::script;
+
+const PROMPT: &str = "omnimind> ";
+const MAX_HISTORY: usize = 1000;
+// Leave the REPL; `quit` as a command only explains how to stop the server.
+const EXIT_WORDS: &[&str] = &["exit", "quit"];
+
+// Runs until `exit` or Ctrl-D; returns the process exit code.
+pub async fn run(target: Target) -> i32 {
+    let config = config::current();
+    let mut runner = match script::connect(&target, "repl").await {
+        Ok(runner) => runner,
+        Err(exit_code) => return exit_code,
+    };
+    let editor_config = rustyline::Config::builder().max_history_size(MAX_HISTORY).map(|builder| builder.auto_add_history(false).build());
+    let mut editor = match editor_config.and_then(Editor::<OmniHelper, DefaultHistory>::with_config) {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("❌ Cannot start the line editor: {}", e);
+            return 1;
+        }
+    };
+    // Note files are only ours to list when the commands run here.
+    editor.set_helper(Some(OmniHelper { filenames: FilenameCompleter::new(), notes: matches!(target, Target::Local) }));
+    let history_path = config.storage.repl_history_file.clone();
+    let _ = editor.load_history(&history_path); // Missing on first use
+
+    match &target {
+        Target::Local => println!("OmniMind REPL. Type 'help' for commands, Tab to complete, 'exit' or Ctrl-D to leave."),
+        Target::Remote(server) => println!("OmniMind REPL on {} (shared session). Type 'help' for commands, Tab to complete, 'exit' or Ctrl-D to leave.", server.url()),
+    }
+    loop {
+        // readline blocks until the user presses Enter; keep that off the async runtime.
+        let Ok((returned, line)) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline(PROMPT);
+            (editor, line)
+        })
+        .await
+        else {
+            return 1;
+        };
+        editor = returned;
+
+        let line = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue, // Ctrl-C clears the line
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                break;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+        if EXIT_WORDS.contains(&line.to_lowercase().as_str()) {
+            break;
+        }
+
+        let response = runner.run(line, |event| println!("{}", output::render_progress(event))).await;
+        println!("{}", output::render(&response));
+    }
+    runner.close().await;
+
+    if let Some(parent) = history_path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = editor.save_history(&history_path) {
+        eprintln!("⚠️  Could not save history to '{}': {}", history_path.display(), e);
+    }
+    0
+}
+
+// --- Completion ---
+
+// OmniHelper: Tab completion for the REPL
+struct OmniHelper {
+    filenames: FilenameCompleter,
+    notes: bool, // Offer note titles for path arguments
+}
+
+impl Completer for OmniHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let word_start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &before[word_start..];
+        if word_start == 0 {
+            return Ok((0, command_candidates(word)));
+        }
+
+        // Which argument is being typed, after resolving an alias to its command.
+        let expanded = registry::expand_alias(before).unwrap_or_else(|| before.to_string());
+        let mut words = expanded.split_whitespace();
+        let Some(spec) = words.next().and_then(|keyword| registry::find(&keyword.to_lowercase())) else { return Ok((pos, Vec::new())) };
+        let index = words.count() - usize::from(!word.is_empty());
+        let Some(arg) = spec.args.get(index).or_else(|| spec.args.last().filter(|arg| arg.rest)) else { return Ok((pos, Vec::new())) };
+
+        if !arg.choices.is_empty() {
+            let choices = arg.choices.iter().filter(|choice| choice.starts_with(word)).map(|choice| pair(choice, format!("{} ", choice))).collect();
+            return Ok((word_start, choices));
+        }
+        if arg.format != Some("path") {
+            return Ok((pos, Vec::new()));
+        }
+        let (start, mut candidates) = self.filenames.complete(line, pos, ctx)?;
+        if start == word_start && self.notes {
+            candidates.splice(0..0, note_candidates(word));
+        }
+        Ok((start, candidates))
+    }
+}
+
+fn pair(display: &str, replacement: String) -> Pair {
+    Pair { display: display.to_string(), replacement }
+}
+
+// Built-in commands (except the server-only `quit`), aliases and the REPL's exit words.
+fn command_candidates(prefix: &str) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    let aliases = config::current().aliases.keys().cloned().collect::<Vec<_>>();
+    let mut names: Vec<String> = registry::COMMANDS.iter().filter(|spec| spec.name != "quit").map(|spec| spec.name.to_string()).collect();
+    names.extend(aliases);
+    names.extend(EXIT_WORDS.iter().map(|word| word.to_string()));
+    names.sort();
+    names.dedup();
+    names.into_iter().filter(|name| name.to_lowercase().starts_with(&prefix)).map(|name| pair(&name, format!("{} ", name))).collect()
+}
+
+// Notes whose title or id starts with the word, completed to the note's path.
+fn note_candidates(prefix: &str) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    let Some(serde_json::Value::Array(notes)) = commands::list_notes_for_api().ok().and_then(|response| response.data) else { return Vec::new() };
+    notes
+        .iter()
+        .filter_map(|note| Some((note["title"].as_str()?, note["id"].as_str()?, note["path"].as_str()?)))
+        .filter(|(title, id, _)| title.to_lowercase().starts_with(&prefix) || id.to_lowercase().starts_with(&prefix))
+        .map(|(title, _, path)| pair(&format!("{} (note)", title), path.to_string()))
+        .collect()
+}
+
+impl Hinter for OmniHelper {
+    type Hint = String;
+}
+
+impl Highlighter for OmniHelper {}
+
+impl Validator for OmniHelper {}
+
+impl Helper for OmniHelper {}
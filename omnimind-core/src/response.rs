@@ -0,0 +1,151 @@
+// src/response.rs for omnimind-core
+// CommandResponse and its typed status and machine-readable error codes.
+use actix_web::http::StatusCode;
+use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+
+use crate::nlu::NluError;
+use crate::planner::PlanError;
+
+// CommandStatus: Outcome of a command, serialized as "success" / "error" / "info"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    Success,
+    Error,
+    Info,
+}
+
+// ErrorCode: Stable identifiers clients can match on instead of message text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    EmptyCommand,
+    UnknownCommand,
+    InvalidArguments,
+    NotFound,
+    PathNotFound,
+    NotADirectory,
+    NotAFile,
+    InvalidNoteTitle,
+    NoteExists,
+    IoError,
+    IpfsUnreachable,
+    IpfsError,
+    InvalidCid,
+    NluUnavailable,
+    NluTimeout,
+    NluInvalidResponse,
+    NluError,
+    NluLowConfidence,
+    UnresolvedStep,
+    Unauthorized,
+    Forbidden,
+    AuditTampered,
+    RateLimited,
+    CommandTooLong,
+    UploadTooLarge,
+    NluBusy,
+    NotReady,
+    ServerUnreachable,
+    ConfigError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn http_status(self) -> StatusCode {
+        match self {
+            ErrorCode::EmptyCommand
+            | ErrorCode::UnknownCommand
+            | ErrorCode::InvalidArguments
+            | ErrorCode::NotADirectory
+            | ErrorCode::NotAFile
+            | ErrorCode::InvalidNoteTitle
+            | ErrorCode::InvalidCid => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NoteExists | ErrorCode::AuditTampered => StatusCode::CONFLICT,
+            ErrorCode::CommandTooLong | ErrorCode::UploadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::NotFound | ErrorCode::PathNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::NluLowConfidence | ErrorCode::UnresolvedStep => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::IpfsUnreachable | ErrorCode::IpfsError | ErrorCode::NluInvalidResponse | ErrorCode::NluError => StatusCode::BAD_GATEWAY,
+            ErrorCode::NluUnavailable | ErrorCode::NluBusy | ErrorCode::NotReady | ErrorCode::ServerUnreachable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::NluTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ErrorCode::IoError | ErrorCode::ConfigError | ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Process exit code for `run` and `script`, grouped like the HTTP statuses.
+    pub fn exit_code(self) -> i32 {
+        match self.http_status() {
+            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE => 2,
+            StatusCode::NOT_FOUND => 3,
+            StatusCode::UNPROCESSABLE_ENTITY => 4,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => 5,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => 6,
+            _ => 1,
+        }
+    }
+}
+
+impl From<&NluError> for ErrorCode {
+    fn from(error: &NluError) -> Self {
+        match error {
+            NluError::Unavailable(_) => ErrorCode::NluUnavailable,
+            NluError::Timeout(_) => ErrorCode::NluTimeout,
+            NluError::Busy(_) => ErrorCode::NluBusy,
+            NluError::InvalidResponse(_) => ErrorCode::NluInvalidResponse,
+            NluError::Service(_) => ErrorCode::NluError,
+        }
+    }
+}
+
+impl From<&PlanError> for ErrorCode {
+    fn from(error: &PlanError) -> Self {
+        match error {
+            PlanError::Empty => ErrorCode::EmptyCommand,
+            PlanError::Unresolved(_) | PlanError::MissingOutput { .. } => ErrorCode::UnresolvedStep,
+            PlanError::Nlu(nlu_error) => ErrorCode::from(nlu_error),
+        }
+    }
+}
+
+// CommandResponse: Unified JSON structure from our API to client
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CommandResponse {
+    pub status: CommandStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>, // Set when status is "error"
+    pub message: String,
+    /// Command-specific result; its fields (e.g. "path", "cid") are what plan steps hand on.
+    pub data: Option<serde_json::Value>,
+}
+
+impl CommandResponse {
+    pub fn success(message: impl Into<String>, data: Option<serde_json::Value>) -> Self {
+        CommandResponse { status: CommandStatus::Success, code: None, message: message.into(), data }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        CommandResponse { status: CommandStatus::Info, code: None, message: message.into(), data: None }
+    }
+
+    // HTTP status for the API: 200 unless the command failed.
+    pub fn http_status(&self) -> StatusCode {
+        match (self.status, self.code) {
+            (CommandStatus::Error, Some(code)) => code.http_status(),
+            (CommandStatus::Error, None) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::OK,
+        }
+    }
+
+    // 0 unless the command failed (see ErrorCode::exit_code).
+    pub fn exit_code(&self) -> i32 {
+        match (self.status, self.code) {
+            (CommandStatus::Error, Some(code)) => code.exit_code(),
+            (CommandStatus::Error, None) => 1,
+            _ => 0,
+        }
+    }
+}
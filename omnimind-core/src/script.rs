@@ -0,0 +1,273 @@
+// src/script.rs for omnimind-core
+// Non-interactive execution for cron jobs and shell scripts, without the HTTP
+// server: `run "<command>"` executes one command and `script file.omni` a file of
+// them. Output is human-readable or JSON, and the exit code reflects the result
+// (see ErrorCode::exit_code). `client script` runs a script on a running server.
+//
+// Script syntax: one command per line. Blank lines and lines starting with '#'
+// are skipped, `set name = value` defines a variable, and $name or ${name} in
+// later lines is replaced by its value ($$ is a literal $). Execution stops at
+// the first failing line unless --keep-going is given.
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use crate::cli::OutputFormat;
+use crate::client::{Runner, Target};
+use crate::error::{self, OmniError};
+use crate::output;
+use crate::progress::ProgressEvent;
+use crate::response::{CommandResponse, CommandStatus};
+
+// ScriptLine: One meaningful line of a script, with its 1-based line number
+enum ScriptLine {
+    Set { number: usize, name: String, value: String },
+    Command { number: usize, text: String },
+}
+
+// Runs a single command; returns the process exit code.
+pub async fn run_command(command: &str, format: OutputFormat) -> i32 {
+    let mut runner = match connect(&Target::Local, "run").await {
+        Ok(runner) => runner,
+        Err(exit_code) => return exit_code,
+    };
+    let response = runner.run(command, |event| report_progress(format, event)).await;
+    print_response(format, &response);
+    runner.close().await;
+    response.exit_code()
+}
+
+pub fn print_response(format: OutputFormat, response: &CommandResponse) {
+    match format {
+        OutputFormat::Human => println!("{}", output::render(response)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(response).unwrap_or_default()),
+    }
+}
+
+// Runs a script file (`-` for standard input); returns the exit code of the
+// first failing line, or 0. `vars` are NAME=VALUE pairs that take precedence
+// over the script's own `set` lines, so scripts can declare defaults.
+pub async fn run_script(target: &Target, file: &Path, vars: &[String], keep_going: bool, format: OutputFormat) -> i32 {
+    let text = if file == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        std::fs::read_to_string(file)
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("❌ Cannot read script '{}': {}", file.display(), e);
+            return 1;
+        }
+    };
+    // Syntax errors are reported before anything runs.
+    let (lines, overrides) = match parse_script(&text).and_then(|lines| Ok((lines, parse_vars(vars)?))) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 2;
+        }
+    };
+
+    let mut runner = match connect(target, "script").await {
+        Ok(runner) => runner,
+        Err(exit_code) => return exit_code,
+    };
+    let mut variables = overrides.clone();
+    let (mut ran, mut failed, mut exit_code) = (0, 0, 0);
+    for line in &lines {
+        let (number, command, result) = match line {
+            ScriptLine::Set { number, name, value } => {
+                if overrides.contains_key(name) {
+                    continue;
+                }
+                match substitute(value, &variables) {
+                    Ok(value) => {
+                        variables.insert(name.clone(), value);
+                        continue;
+                    }
+                    Err(e) => (*number, format!("set {} = {}", name, value), error::into_response(Err(e))),
+                }
+            }
+            ScriptLine::Command { number, text } => match substitute(text, &variables) {
+                Ok(command) => {
+                    if format == OutputFormat::Human {
+                        println!("[line {}] {}", number, command);
+                    }
+                    let response = runner.run(&command, |event| report_progress(format, event)).await;
+                    (*number, command, response)
+                }
+                Err(e) => (*number, text.clone(), error::into_response(Err(e))),
+            },
+        };
+        ran += 1;
+        report_line(format, number, &command, &result);
+        if result.status == CommandStatus::Error {
+            failed += 1;
+            if exit_code == 0 {
+                exit_code = result.exit_code();
+            }
+            if !keep_going {
+                eprintln!("Script stopped at line {} after {} command(s).", number, ran);
+                runner.close().await;
+                return exit_code;
+            }
+        }
+    }
+    runner.close().await;
+    if format == OutputFormat::Human {
+        eprintln!("Script finished: {} command(s) run, {} failed.", ran, failed);
+    }
+    exit_code
+}
+
+// Fails with the exit code for the error, e.g. 6 when the server is unreachable.
+pub async fn connect(target: &Target, session: &str) -> Result<Runner, i32> {
+    target.connect(session).await.map_err(|e| {
+        eprintln!("❌ {}", e);
+        e.code().exit_code()
+    })
+}
+
+// Progress goes to stderr so stdout carries only results; JSON output skips it.
+fn report_progress(format: OutputFormat, event: &ProgressEvent) {
+    if format == OutputFormat::Human {
+        eprintln!("{}", output::render_progress(event));
+    }
+}
+
+// Human output is a transcript; JSON output is one object per line (JSON Lines).
+fn report_line(format: OutputFormat, number: usize, command: &str, response: &CommandResponse) {
+    match format {
+        OutputFormat::Human => println!("{}", output::render(response)),
+        OutputFormat::Json => println!("{}", serde_json::json!({ "line": number, "command": command, "response": response })),
+    }
+}
+
+// --- Parsing ---
+
+fn parse_script(text: &str) -> Result<Vec<ScriptLine>, OmniError> {
+    let mut lines = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let (number, line) = (index + 1, line.trim());
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        if !keyword.eq_ignore_ascii_case("set") {
+            lines.push(ScriptLine::Command { number, text: line.to_string() });
+            continue;
+        }
+        let (name, value) = line[keyword.len()..]
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), unquote(value.trim())))
+            .filter(|(name, _)| is_variable_name(name))
+            .ok_or_else(|| OmniError::InvalidArguments(format!("Line {}: expected `set name = value` (names are letters, digits and _).", number)))?;
+        lines.push(ScriptLine::Set { number, name: name.to_string(), value: value.to_string() });
+    }
+    Ok(lines)
+}
+
+fn parse_vars(vars: &[String]) -> Result<HashMap<String, String>, OmniError> {
+    vars.iter()
+        .map(|var| {
+            var.split_once('=')
+                .filter(|(name, _)| is_variable_name(name))
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .ok_or_else(|| OmniError::InvalidArguments(format!("--var '{}': expected NAME=VALUE.", var)))
+        })
+        .collect()
+}
+
+fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// `set title = "Weekly report"` keeps the spaces but not the quotes.
+fn unquote(value: &str) -> &str {
+    ['"', '\''].iter().find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote)).unwrap_or(value)
+}
+
+// Replaces $name and ${name}; a '$' not followed by a name is kept as is.
+fn substitute(text: &str, variables: &HashMap<String, String>) -> Result<String, OmniError> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+            continue;
+        }
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find('}').ok_or_else(|| OmniError::InvalidArguments(format!("Unclosed '${{' in '{}'.", text)))?;
+                (&braced[..end], end + 2)
+            }
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        if name.is_empty() {
+            result.push('$');
+            rest = after;
+            continue;
+        }
+        let value = variables.get(name).ok_or_else(|| OmniError::InvalidArguments(format!("Undefined variable '{}'; define it with `set {} = ...` or --var {}=....", name, name, name)))?;
+        result.push_str(value);
+        rest = &after[consumed..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn substitutes_plain_and_braced_names() {
+        let vars = variables(&[("title", "Weekly report"), ("dir", "/tmp")]);
+        assert_eq!(substitute("create_note $title", &vars).unwrap(), "create_note Weekly report");
+        assert_eq!(substitute("ls ${dir}/logs", &vars).unwrap(), "ls /tmp/logs");
+        assert_eq!(substitute("echo $dir-$title!", &vars).unwrap(), "echo /tmp-Weekly report!");
+    }
+
+    #[test]
+    fn keeps_dollars_that_are_not_variables() {
+        let vars = variables(&[("price", "5")]);
+        assert_eq!(substitute("echo costs $$$price", &vars).unwrap(), "echo costs $5");
+        assert_eq!(substitute("echo $ and $1x", &variables(&[("1x", "no")])).unwrap(), "echo $ and no");
+        assert_eq!(substitute("echo 100$", &vars).unwrap(), "echo 100$");
+    }
+
+    #[test]
+    fn undefined_or_unclosed_variables_are_errors() {
+        assert!(matches!(substitute("echo $missing", &HashMap::new()), Err(OmniError::InvalidArguments(_))));
+        assert!(matches!(substitute("echo ${title", &variables(&[("title", "x")])), Err(OmniError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn parses_set_lines_comments_and_commands() {
+        let lines = parse_script("# nightly\n\nset title = \"Weekly report\"\nSET dir='/tmp'\ncreate_note $title\n").unwrap();
+        assert!(matches!(&lines[0], ScriptLine::Set { number: 3, name, value } if name == "title" && value == "Weekly report"));
+        assert!(matches!(&lines[1], ScriptLine::Set { number: 4, name, value } if name == "dir" && value == "/tmp"));
+        assert!(matches!(&lines[2], ScriptLine::Command { number: 5, text } if text == "create_note $title"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn rejects_malformed_set_lines_and_vars() {
+        assert!(parse_script("echo hi\nset 9lives = 1").is_err());
+        assert!(parse_script("set title").is_err());
+        assert_eq!(parse_vars(&["title=a=b".to_string()]).unwrap()["title"], "a=b");
+        assert!(parse_vars(&["no-equals".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+// src/server.rs for omnimind-core
+// Where and how the API server listens: a TCP address (plain HTTP, or HTTPS with
+// rustls from PEM files), an optional Unix domain socket for local-only clients,
+// and the browser origins CORS allows.
+use std::path::Path;
+use std::sync::Arc;
+
+use actix_cors::Cors;
+use actix_web::http::header;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+
+use crate::config::{self, ServerConfig};
+use crate::error::OmniError;
+use crate::logging;
+
+// TCP address to bind, or None when server.bind is "none" (Unix socket only).
+pub fn tcp_address(config: &ServerConfig) -> Option<&str> {
+    Some(config.bind.as_str()).filter(|bind| !bind.eq_ignore_ascii_case("none"))
+}
+
+// Human-readable addresses for the startup banner.
+pub fn listen_urls(config: &ServerConfig) -> Vec<String> {
+    let mut urls = Vec::new();
+    if let Some(bind) = tcp_address(config) {
+        urls.push(format!("{}://{}", if config.tls_cert.is_some() { "https" } else { "http" }, bind));
+    }
+    if let Some(path) = &config.unix_socket {
+        urls.push(format!("unix:{}", path.display()));
+    }
+    urls
+}
+
+// --- CORS ---
+
+// Origins are checked against server.cors_origins on every request, so config
+// reloads apply without rebuilding the middleware.
+pub fn cors() -> Cors {
+    Cors::default()
+        .allowed_methods(vec!["GET", "POST"])
+        .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE, logging::REQUEST_ID_HEADER])
+        .expose_headers(vec![logging::REQUEST_ID_HEADER, header::RETRY_AFTER])
+        .max_age(3600)
+        .allowed_origin_fn(|origin, _req| {
+            let origins = &config::current().server.cors_origins;
+            origin.to_str().is_ok_and(|origin| origins.iter().any(|pattern| pattern == "*" || origin_matches(pattern, origin)))
+        })
+}
+
+// Each '*' in a pattern stands for any run of characters other than '/', so
+// "https://*.vercel.app" allows every Vercel preview deployment.
+fn origin_matches(pattern: &str, origin: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern.eq_ignore_ascii_case(origin),
+        Some((prefix, rest)) => {
+            if !origin.get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix)) {
+                return false;
+            }
+            let tail = &origin[prefix.len()..];
+            // Try every split of the tail between the wildcard and the rest of the pattern.
+            tail.char_indices().map(|(i, _)| i).chain(std::iter::once(tail.len()))
+                .take_while(|&i| !tail[..i].contains('/'))
+                .any(|i| origin_matches(rest, &tail[i..]))
+        }
+    }
+}
+
+// --- Listeners ---
+
+// None unless server.tls_cert and server.tls_key are set (config.rs checks they come together).
+pub fn rustls_config(config: &ServerConfig) -> Result<Option<rustls::ServerConfig>, OmniError> {
+    let (Some(cert), Some(key)) = (&config.tls_cert, &config.tls_key) else { return Ok(None) };
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| OmniError::Config(format!("cannot read TLS certificate '{}': {}", cert.display(), e)))?;
+    if certs.is_empty() {
+        return Err(OmniError::Config(format!("no certificate found in '{}'.", cert.display())));
+    }
+    let key = PrivateKeyDer::from_pem_file(key).map_err(|e| OmniError::Config(format!("cannot read TLS private key '{}': {}", key.display(), e)))?;
+
+    rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map(Some)
+        .map_err(|e| OmniError::Config(format!("TLS certificate/key rejected: {}", e)))
+}
+
+// A socket file left behind by a previous run would make binding fail; anything
+// else at that path is left alone and reported.
+#[cfg(unix)]
+pub fn remove_stale_socket(path: &Path) -> Result<(), OmniError> {
+    use std::os::unix::fs::FileTypeExt;
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path).map_err(|e| OmniError::io(format!("Failed to remove stale socket '{}'", path.display()), e)),
+        Ok(_) => Err(OmniError::Config(format!("'{}' exists and is not a socket.", path.display()))),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_origins_match_case_insensitively() {
+        assert!(origin_matches("http://localhost:3000", "http://localhost:3000"));
+        assert!(origin_matches("https://App.example.com", "https://app.example.com"));
+        assert!(!origin_matches("http://localhost:3000", "http://localhost:3001"));
+        assert!(!origin_matches("https://example.com", "http://example.com"));
+    }
+
+    #[test]
+    fn a_wildcard_covers_one_host_label_run_but_not_a_slash() {
+        assert!(origin_matches("https://*.vercel.app", "https://my-app-git-main.vercel.app"));
+        assert!(origin_matches("https://*.vercel.app", "https://a.b.vercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://vercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://evil.com/.vercel.app"));
+        assert!(!origin_matches("https://*.vercel.app", "https://x.vercel.app.evil.com"));
+    }
+
+    #[test]
+    fn several_wildcards_and_ports() {
+        assert!(origin_matches("http://localhost:*", "http://localhost:5173"));
+        assert!(origin_matches("https://*.*.example.com", "https://a.b.example.com"));
+        assert!(!origin_matches("http://localhost:*", "http://localhost.evil.com/:1"));
+    }
+}
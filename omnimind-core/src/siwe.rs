@@ -0,0 +1,442 @@
+// src/siwe.rs for omnimind-core
+// Sign-In with Ethereum (EIP-4361). The client fetches a nonce from GET
+// /auth/nonce, has the wallet sign a SIWE message containing it, and posts both
+// to POST /auth/verify. The signature is checked locally (EIP-191 personal_sign,
+// secp256k1 public key recovery) and the wallet gets a session token that the
+// auth middleware accepts like an API token, bound to the wallet address. A valid
+// signature only proves who the wallet is, and the domain in the message is chosen
+// by whoever signs it, so only wallets listed in [auth.wallets] may sign in (or
+// any wallet, if auth.wallet_scopes is set), with the scopes configured there.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, FixedOffset, Utc};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use rand::distr::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use sha2::{Digest as _, Sha256};
+use sha3::Keccak256;
+use utoipa::ToSchema;
+
+use crate::api;
+use crate::auth::Principal;
+use crate::config::{self, AuthConfig};
+use crate::error::OmniError;
+use crate::response::CommandResponse;
+use crate::AppState;
+
+// How long a nonce may wait for its signed message.
+const NONCE_TTL: Duration = Duration::from_secs(10 * 60);
+// Outstanding nonces kept at most; the oldest are dropped beyond this.
+const MAX_PENDING_NONCES: usize = 10_000;
+// Longest a wallet session lasts, even if the message asks for more.
+const MAX_SESSION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const SESSION_TOKEN_PREFIX: &str = "omni_siwe_";
+
+// WalletSession: A signed-in wallet, stored under the hash of its token
+struct WalletSession {
+    address: String,
+    chain_id: u64,
+    expires: Instant,
+}
+
+// SiweState: Issued nonces and wallet sessions (in memory; a restart signs everyone out).
+// Accepted domains and wallets are read from the [auth] config on each use, so a
+// config reload applies to sessions that already exist; a wallet removed from the
+// allowlist is signed out.
+#[derive(Default)]
+pub struct SiweState {
+    nonces: Mutex<HashMap<String, Instant>>,
+    sessions: Mutex<HashMap<String, WalletSession>>,
+}
+
+impl SiweState {
+    fn issue_nonce(&self) -> Result<String, OmniError> {
+        let nonce: String = rand::rng().sample_iter(&Alphanumeric).take(17).map(char::from).collect();
+        let mut nonces = self.nonces.lock().map_err(|_| OmniError::Internal("SIWE nonce lock error".to_string()))?;
+        nonces.retain(|_, issued| issued.elapsed() < NONCE_TTL);
+        if nonces.len() >= MAX_PENDING_NONCES {
+            if let Some(oldest) = nonces.iter().min_by_key(|(_, issued)| **issued).map(|(nonce, _)| nonce.clone()) {
+                nonces.remove(&oldest);
+            }
+        }
+        nonces.insert(nonce.clone(), Instant::now());
+        Ok(nonce)
+    }
+
+    // A nonce is good for one verification attempt, successful or not.
+    fn consume_nonce(&self, nonce: &str) -> bool {
+        self.nonces.lock().ok().and_then(|mut nonces| nonces.remove(nonce)).is_some_and(|issued| issued.elapsed() < NONCE_TTL)
+    }
+
+    fn start_session(&self, address: &str, chain_id: u64, ttl: Duration) -> Result<String, OmniError> {
+        let token = format!("{}{}", SESSION_TOKEN_PREFIX, hex::encode(rand::random::<[u8; 32]>()));
+        let mut sessions = self.sessions.lock().map_err(|_| OmniError::Internal("SIWE session lock error".to_string()))?;
+        let now = Instant::now();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(hash_token(&token), WalletSession { address: address.to_string(), chain_id, expires: now + ttl });
+        Ok(token)
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<Principal> {
+        if !token.starts_with(SESSION_TOKEN_PREFIX) {
+            return None;
+        }
+        let sessions = self.sessions.lock().ok()?;
+        let session = sessions.get(&hash_token(token)).filter(|session| session.expires > Instant::now())?;
+        let scopes = wallet_scopes(&config::current().auth, &session.address)?;
+        Some(Principal::wallet(&session.address, session.chain_id, scopes))
+    }
+}
+
+// The scopes `address` signs in with, or None if it may not sign in.
+fn wallet_scopes(auth: &AuthConfig, address: &str) -> Option<Vec<String>> {
+    if let Some((_, scopes)) = auth.wallets.iter().find(|(listed, _)| listed.eq_ignore_ascii_case(address)) {
+        return Some(scopes.clone());
+    }
+    (!auth.wallet_scopes.is_empty()).then(|| auth.wallet_scopes.clone())
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+// --- EIP-4361 messages ---
+
+// SiweMessage: The fields of a SIWE message we check
+#[derive(Debug)]
+struct SiweMessage {
+    domain: String,
+    address: String,
+    uri: String,
+    version: String,
+    chain_id: u64,
+    nonce: String,
+    issued_at: DateTime<FixedOffset>,
+    expiration_time: Option<DateTime<FixedOffset>>,
+    not_before: Option<DateTime<FixedOffset>>,
+}
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+
+fn parse_message(message: &str) -> Result<SiweMessage, OmniError> {
+    let invalid = |what: &str| OmniError::InvalidArguments(format!("Invalid SIWE message: {}", what));
+    let mut lines = message.lines();
+    let domain = lines.next().and_then(|line| line.strip_suffix(HEADER_SUFFIX)).ok_or_else(|| invalid("the first line must be '<domain> wants you to sign in with your Ethereum account:'"))?;
+    // An optional "https://" scheme may precede the domain.
+    let domain = domain.split_once("://").map_or(domain, |(_, rest)| rest);
+    let address = lines.next().map(str::trim).filter(|address| is_hex_address(address)).ok_or_else(|| invalid("the second line must be a 0x-prefixed 40-digit address"))?;
+
+    // The statement (if any) sits between blank lines; then come "Key: value" fields.
+    let mut fields = HashMap::new();
+    let mut in_fields = false;
+    for line in lines {
+        if line.starts_with("URI: ") {
+            in_fields = true;
+        }
+        if !in_fields || line.starts_with("- ") {
+            continue; // Statement text and Resources entries
+        }
+        if let Some((key, value)) = line.split_once(": ") {
+            fields.insert(key, value.trim());
+        }
+    }
+    let field = |key: &str| fields.get(key).copied().ok_or_else(|| invalid(&format!("missing '{}'", key)));
+    let time = |key: &str| -> Result<Option<DateTime<FixedOffset>>, OmniError> {
+        fields.get(key).map(|value| DateTime::parse_from_rfc3339(value).map_err(|_| invalid(&format!("'{}' is not an RFC 3339 time", key)))).transpose()
+    };
+
+    let nonce = field("Nonce")?;
+    if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid("the nonce must be at least 8 alphanumeric characters"));
+    }
+    Ok(SiweMessage {
+        domain: domain.to_string(),
+        address: address.to_string(),
+        uri: field("URI")?.to_string(),
+        version: field("Version")?.to_string(),
+        chain_id: field("Chain ID")?.parse().map_err(|_| invalid("'Chain ID' must be a number"))?,
+        nonce: nonce.to_string(),
+        issued_at: time("Issued At")?.ok_or_else(|| invalid("missing 'Issued At'"))?,
+        expiration_time: time("Expiration Time")?,
+        not_before: time("Not Before")?,
+    })
+}
+
+pub fn is_hex_address(address: &str) -> bool {
+    address.strip_prefix("0x").is_some_and(|digits| digits.len() == 40 && digits.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// EIP-55 mixed-case checksum form of a 20-byte address.
+fn checksum_address(address: &[u8]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let digits: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+        })
+        .collect();
+    format!("0x{}", digits)
+}
+
+// Recovers the checksummed address that produced an EIP-191 personal_sign
+// signature (65 bytes r || s || v, v being 27/28 or 0/1) over `message`.
+fn recover_address(message: &str, signature: &str) -> Result<String, OmniError> {
+    let invalid = |what: &str| OmniError::InvalidArguments(format!("Invalid signature: {}", what));
+    let bytes = hex::decode(signature.trim().trim_start_matches("0x")).map_err(|_| invalid("not hex"))?;
+    if bytes.len() != 65 {
+        return Err(invalid("expected 65 bytes"));
+    }
+    let signature = Signature::from_slice(&bytes[..64]).map_err(|_| invalid("malformed r/s"))?;
+    let v = match bytes[64] {
+        27 | 28 => bytes[64] - 27,
+        v => v,
+    };
+    let recovery_id = RecoveryId::try_from(v).map_err(|_| invalid("bad recovery id"))?;
+
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let digest = Keccak256::digest(prefixed.as_bytes());
+    let key = VerifyingKey::recover_from_prehash(&digest, &signature, recovery_id)
+        .map_err(|_| OmniError::Unauthorized("The signature does not match the message.".to_string()))?;
+    Ok(address_of(&key))
+}
+
+// The checksummed address of a public key: the last 20 bytes of its Keccak-256 hash.
+fn address_of(key: &VerifyingKey) -> String {
+    let public_key = key.to_encoded_point(false);
+    let address_hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+    checksum_address(&address_hash[12..])
+}
+
+// Checks everything about a signed message against the [auth] settings; returns
+// the wallet's scopes and how long the session may last.
+fn verify(state: &SiweState, auth: &AuthConfig, message: &SiweMessage, raw_message: &str, signature: &str) -> Result<(Vec<String>, Duration), OmniError> {
+    if !state.consume_nonce(&message.nonce) {
+        return Err(OmniError::Unauthorized("Unknown, used or expired nonce; request a new one from GET /auth/nonce.".to_string()));
+    }
+    if message.version != "1" {
+        return Err(OmniError::InvalidArguments(format!("Unsupported SIWE version '{}'.", message.version)));
+    }
+    if !auth.siwe_domains.iter().any(|domain| domain == &message.domain) {
+        return Err(OmniError::Unauthorized(format!("Sign-in requests for domain '{}' are not accepted here.", message.domain)));
+    }
+    let now = Utc::now();
+    if message.not_before.is_some_and(|not_before| now < not_before) {
+        return Err(OmniError::Unauthorized("The message is not valid yet (Not Before).".to_string()));
+    }
+    if message.issued_at > now + chrono::Duration::minutes(5) {
+        return Err(OmniError::Unauthorized("The message was issued in the future.".to_string()));
+    }
+    let ttl = match message.expiration_time {
+        Some(expires) if expires <= now => return Err(OmniError::Unauthorized("The message has expired.".to_string())),
+        Some(expires) => (expires.with_timezone(&Utc) - now).to_std().unwrap_or_default().min(MAX_SESSION_TTL),
+        None => MAX_SESSION_TTL,
+    };
+
+    let recovered = recover_address(raw_message, signature)?;
+    if recovered != message.address {
+        return Err(if recovered.eq_ignore_ascii_case(&message.address) {
+            OmniError::InvalidArguments(format!("The address must be EIP-55 checksummed: {}", recovered))
+        } else {
+            OmniError::Unauthorized("The signature was not made by the address in the message.".to_string())
+        });
+    }
+    let scopes = wallet_scopes(auth, &recovered).ok_or_else(|| OmniError::Unauthorized(format!("Wallet {} is not allowed to sign in here (see [auth.wallets]).", recovered)))?;
+    Ok((scopes, ttl))
+}
+
+// --- Handlers ---
+
+// SiweVerifyRequest: Body of POST /auth/verify
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SiweVerifyRequest {
+    /// The EIP-4361 message exactly as the wallet signed it.
+    pub message: String,
+    /// 0x-prefixed 65-byte personal_sign signature.
+    pub signature: String,
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/auth/nonce", web::get().to(nonce))
+        .route("/auth/verify", web::post().to(verify_signature))
+        .route("/auth/me", web::get().to(me));
+}
+
+#[utoipa::path(
+    get, path = "/auth/nonce", tag = "auth",
+    responses((status = 200, description = "A single-use nonce (data.nonce) to put in the SIWE message", body = CommandResponse))
+)]
+pub(crate) async fn nonce(app_state: web::Data<AppState>) -> HttpResponse {
+    api::respond(app_state.siwe.issue_nonce().map(|nonce| {
+        CommandResponse::success("Sign a SIWE message containing this nonce.", Some(serde_json::json!({
+            "nonce": nonce,
+            "expires_in_secs": NONCE_TTL.as_secs(),
+            "domains": config::current().auth.siwe_domains,
+        })))
+    }))
+}
+
+#[utoipa::path(
+    post, path = "/auth/verify", tag = "auth",
+    request_body = SiweVerifyRequest,
+    responses(
+        (status = 200, description = "Signed in; data.token is a session token for Authorization: Bearer", body = CommandResponse),
+        (status = 400, description = "Malformed message or signature", body = CommandResponse),
+        (status = 401, description = "Bad signature, nonce, domain or validity period, or a wallet not in [auth.wallets]", body = CommandResponse),
+    )
+)]
+pub(crate) async fn verify_signature(req: web::Json<SiweVerifyRequest>, app_state: web::Data<AppState>) -> HttpResponse {
+    let state = &app_state.siwe;
+    let result = parse_message(&req.message).and_then(|message| {
+        let (scopes, ttl) = verify(state, &config::current().auth, &message, &req.message, &req.signature)?;
+        let token = state.start_session(&message.address, message.chain_id, ttl)?;
+        tracing::info!("Wallet {} signed in (chain {}, {}).", message.address, message.chain_id, message.uri);
+        Ok(CommandResponse::success(format!("Signed in as {}.", message.address), Some(serde_json::json!({
+            "token": token,
+            "address": message.address,
+            "chain_id": message.chain_id,
+            "scopes": scopes,
+            "expires_at": (Utc::now() + chrono::Duration::from_std(ttl).unwrap_or_default()).to_rfc3339(),
+        }))))
+    });
+    api::respond(result)
+}
+
+#[utoipa::path(
+    get, path = "/auth/me", tag = "auth",
+    responses(
+        (status = 200, description = "The caller: token name or wallet address, and its scopes", body = CommandResponse),
+        (status = 401, description = "Missing or invalid token", body = CommandResponse),
+    )
+)]
+pub(crate) async fn me(principal: web::ReqData<Principal>) -> HttpResponse {
+    api::respond(Ok(CommandResponse::success(format!("Authenticated as {}.", principal.name), Some(principal.to_json()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::SigningKey;
+
+    use super::*;
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 32]).expect("valid secret key")
+    }
+
+    // [auth] accepting localhost:3000 and exactly the wallet of `key`.
+    fn auth_allowing(key: &SigningKey) -> AuthConfig {
+        let mut auth = AuthConfig::default();
+        auth.wallets.insert(address_of(key.verifying_key()), vec!["notes:read".to_string()]);
+        auth
+    }
+
+    fn message(domain: &str, address: &str, nonce: &str, expires: DateTime<Utc>) -> String {
+        format!(
+            "{domain}{HEADER_SUFFIX}\n{address}\n\nSign in to OmniMind.\n\nURI: http://{domain}\nVersion: 1\nChain ID: 1\nNonce: {nonce}\nIssued At: {}\nExpiration Time: {}",
+            Utc::now().to_rfc3339(),
+            expires.to_rfc3339(),
+        )
+    }
+
+    // EIP-191 personal_sign, as a wallet would produce it.
+    fn sign(key: &SigningKey, message: &str) -> String {
+        let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&Keccak256::digest(prefixed.as_bytes())).expect("signature");
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        format!("0x{}", hex::encode(bytes))
+    }
+
+    fn check(state: &SiweState, auth: &AuthConfig, raw: &str, signature: &str) -> Result<(Vec<String>, Duration), OmniError> {
+        verify(state, auth, &parse_message(raw)?, raw, signature)
+    }
+
+    fn in_an_hour() -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::hours(1)
+    }
+
+    #[test]
+    fn signed_message_from_an_allowed_wallet_verifies() {
+        let key = signing_key(7);
+        let state = SiweState::default();
+        let raw = message("localhost:3000", &address_of(key.verifying_key()), &state.issue_nonce().unwrap(), in_an_hour());
+        let (scopes, ttl) = check(&state, &auth_allowing(&key), &raw, &sign(&key, &raw)).expect("verified");
+        assert_eq!(scopes, vec!["notes:read".to_string()]);
+        assert!(ttl > Duration::from_secs(3500) && ttl <= Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn unknown_or_reused_nonce_is_rejected() {
+        let key = signing_key(7);
+        let auth = auth_allowing(&key);
+        let state = SiweState::default();
+        let raw = message("localhost:3000", &address_of(key.verifying_key()), "notissuedbyus", in_an_hour());
+        assert!(matches!(check(&state, &auth, &raw, &sign(&key, &raw)), Err(OmniError::Unauthorized(_))));
+
+        let raw = message("localhost:3000", &address_of(key.verifying_key()), &state.issue_nonce().unwrap(), in_an_hour());
+        assert!(check(&state, &auth, &raw, &sign(&key, &raw)).is_ok());
+        assert!(matches!(check(&state, &auth, &raw, &sign(&key, &raw)), Err(OmniError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn expired_message_is_rejected() {
+        let key = signing_key(7);
+        let state = SiweState::default();
+        let raw = message("localhost:3000", &address_of(key.verifying_key()), &state.issue_nonce().unwrap(), Utc::now() - chrono::Duration::minutes(1));
+        let error = check(&state, &auth_allowing(&key), &raw, &sign(&key, &raw)).unwrap_err();
+        assert_eq!(error.to_string(), "The message has expired.");
+    }
+
+    #[test]
+    fn wrong_domain_is_rejected() {
+        let key = signing_key(7);
+        let state = SiweState::default();
+        let raw = message("evil.example", &address_of(key.verifying_key()), &state.issue_nonce().unwrap(), in_an_hour());
+        let error = check(&state, &auth_allowing(&key), &raw, &sign(&key, &raw)).unwrap_err();
+        assert!(error.to_string().contains("'evil.example'"), "{}", error);
+    }
+
+    #[test]
+    fn wallet_not_in_the_allowlist_is_rejected() {
+        let (allowed, stranger) = (signing_key(7), signing_key(8));
+        let state = SiweState::default();
+        let raw = message("localhost:3000", &address_of(stranger.verifying_key()), &state.issue_nonce().unwrap(), in_an_hour());
+        let error = check(&state, &auth_allowing(&allowed), &raw, &sign(&stranger, &raw)).unwrap_err();
+        assert!(error.to_string().contains("not allowed to sign in"), "{}", error);
+    }
+
+    #[test]
+    fn signature_by_another_key_is_rejected() {
+        let (key, other) = (signing_key(7), signing_key(8));
+        let state = SiweState::default();
+        let raw = message("localhost:3000", &address_of(key.verifying_key()), &state.issue_nonce().unwrap(), in_an_hour());
+        let error = check(&state, &auth_allowing(&key), &raw, &sign(&other, &raw)).unwrap_err();
+        assert_eq!(error.to_string(), "The signature was not made by the address in the message.");
+    }
+
+    #[test]
+    fn parses_fields_and_rejects_malformed_messages() {
+        let raw = message("https://localhost:3000", "0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B", "abcdefgh12", in_an_hour());
+        let parsed = parse_message(&raw).expect("parsed");
+        assert_eq!(parsed.domain, "localhost:3000");
+        assert_eq!(parsed.address, "0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B");
+        assert_eq!((parsed.chain_id, parsed.nonce.as_str(), parsed.version.as_str()), (1, "abcdefgh12", "1"));
+
+        assert!(parse_message("hello\n0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B").is_err());
+        assert!(parse_message(&raw.replace("0xAb5801a7D398351b8bE11C439e05C5B3259aeC9B", "0x1234")).is_err());
+        assert!(parse_message(&raw.replace("Nonce: abcdefgh12", "Nonce: short")).is_err());
+        assert!(parse_message(&raw.replace("Chain ID: 1", "Chain ID: one")).is_err());
+    }
+
+    #[test]
+    fn checksums_addresses_per_eip55() {
+        let address = hex::decode("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        assert_eq!(checksum_address(&address), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+    }
+}
@@ -0,0 +1,163 @@
+// src/ws.rs for omnimind-core
+// GET /ws: interactive sessions over one WebSocket. The client sends commands
+// (a JSON {"id", "raw_command"} or just the command text); for each one the
+// server answers "accepted", then any "progress" / "output" / "clarification"
+// events while it runs, then a "result" carrying the usual CommandResponse.
+// Commands in a session run one at a time, and a clarification question is
+// answered by the next command sent in the same session. Each connection gets a
+// session of its own, unless it asks for ?session=shared to join the session its
+// caller's POST /command requests use (as `omnimind-core client` does, to share
+// it with the React client signed in with the same token).
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use serde::Deserialize;
+use tracing::Instrument;
+
+use crate::api;
+use crate::auth::{self, Principal};
+use crate::error::OmniError;
+use crate::limits;
+use crate::logging;
+use crate::metrics;
+use crate::progress::Progress;
+use crate::response::{CommandResponse, ErrorCode};
+use crate::{execute_command, AppState, Session};
+
+// ClientMessage: One command sent by the client
+#[derive(Debug, Deserialize)]
+struct ClientMessage {
+    #[serde(default)]
+    id: Option<serde_json::Value>, // Echoed back on every event for this command
+    raw_command: String,
+}
+
+// SessionQuery: ?session=shared joins the session of POST /command
+#[derive(Debug, Deserialize)]
+pub struct SessionQuery {
+    #[serde(default)]
+    session: Option<String>,
+}
+
+pub async fn ws_session(req: HttpRequest, body: web::Payload, query: web::Query<SessionQuery>, app_state: web::Data<AppState>, principal: web::ReqData<Principal>) -> Result<HttpResponse, actix_web::Error> {
+    let shared = match query.session.as_deref() {
+        None => false,
+        Some("shared") => true,
+        Some(other) => {
+            let error = OmniError::InvalidArguments(format!("Unknown session '{}'; leave it out for a new session or use 'shared'.", other));
+            return Ok(api::respond(Err(error)));
+        }
+    };
+    let (response, ws, stream) = actix_ws::handle(&req, body)?;
+    // The session outlives the upgrade request; its span keeps that request's ID,
+    // and each command gets its own (see execute_command).
+    let span = tracing::info_span!(parent: None, "ws_session", request_id = logging::request_id().map(tracing::field::display), session = tracing::field::Empty);
+    let caller = auth::caller_key(&principal, req.peer_addr());
+    actix_web::rt::spawn(run_session(ws, stream, app_state, principal.into_inner(), caller, shared).instrument(span));
+    Ok(response)
+}
+
+// Every command in the session runs with the permissions of the token it was opened
+// with, and counts against that caller's rate limit (`caller`, see limits.rs).
+async fn run_session(mut ws: actix_ws::Session, mut stream: actix_ws::MessageStream, app_state: web::Data<AppState>, principal: Principal, caller: String, shared: bool) {
+    let session = if shared { app_state.http_session(&caller) } else { Arc::new(Session::new(uuid::Uuid::new_v4().to_string())) };
+    let session = &*session;
+    tracing::Span::current().record("session", tracing::field::display(&session.id));
+    let _active = metrics::ActiveSession::open();
+    tracing::info!("WebSocket session {} opened.", session.id);
+    if send(&mut ws, serde_json::json!({ "type": "session", "session_id": session.id })).await.is_err() {
+        return;
+    }
+
+    let mut close_reason = None;
+    while let Some(message) = stream.recv().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text.to_string(),
+            Ok(Message::Ping(bytes)) => {
+                if ws.pong(&bytes).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Ok(Message::Close(reason)) => {
+                close_reason = reason;
+                break;
+            }
+            Ok(Message::Binary(_)) => {
+                if reject(&mut ws, "Binary messages are not supported; send text or JSON.").await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!("WebSocket session {} protocol error: {}", session.id, e);
+                break;
+            }
+        };
+
+        // Plain text is a command line; JSON must at least carry raw_command.
+        let sent = match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(command) => run_command(&mut ws, session, &principal, &caller, &app_state, command).await,
+            Err(_) if !text.trim_start().starts_with('{') => run_command(&mut ws, session, &principal, &caller, &app_state, ClientMessage { id: None, raw_command: text }).await,
+            Err(e) => reject(&mut ws, &format!("Invalid message: {}", e)).await,
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+
+    tracing::info!("WebSocket session {} closed.", session.id);
+    let _ = ws.close(close_reason).await;
+}
+
+// Runs one command, streaming its progress events before the final result.
+async fn run_command(ws: &mut actix_ws::Session, session: &Session, principal: &Principal, caller: &str, app_state: &web::Data<AppState>, command: ClientMessage) -> Result<(), actix_ws::Closed> {
+    let id = command.id.unwrap_or(serde_json::Value::Null);
+    if let Err(error) = limits::check_rate(caller).and_then(|_| limits::check_command_length(&command.raw_command)) {
+        return send(ws, serde_json::json!({ "type": "result", "id": id, "response": CommandResponse::from(error) })).await;
+    }
+    send(ws, serde_json::json!({ "type": "accepted", "id": id, "raw_command": command.raw_command })).await?;
+
+    let (progress, mut events) = Progress::channel();
+    let mut forward_ws = ws.clone();
+    let forward_id = id.clone();
+    let forwarder = actix_web::rt::spawn(async move {
+        while let Some(event) = events.recv().await {
+            let mut message = serde_json::json!(event);
+            message["id"] = forward_id.clone();
+            if send(&mut forward_ws, message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let response = execute_command(&command.raw_command, app_state.clone(), session, principal, &progress).await;
+    drop(progress); // Ends the forwarder once the remaining events are sent
+    let _ = forwarder.await;
+
+    if response.code == Some(ErrorCode::NluLowConfidence) {
+        if let Some(clarification) = response.data.as_ref().and_then(|data| data.get("clarification")) {
+            send(ws, serde_json::json!({
+                "type": "clarification",
+                "id": id,
+                "question": response.message,
+                "utterance": clarification.get("utterance"),
+                "suggested_intent": clarification.get("suggested_intent"),
+            }))
+            .await?;
+        }
+    }
+    send(ws, serde_json::json!({ "type": "result", "id": id, "response": response })).await
+}
+
+// A message we could not treat as a command still gets a result, so clients waiting on one do not hang.
+async fn reject(ws: &mut actix_ws::Session, reason: &str) -> Result<(), actix_ws::Closed> {
+    let response = CommandResponse::from(OmniError::InvalidArguments(reason.to_string()));
+    send(ws, serde_json::json!({ "type": "result", "id": null, "response": response })).await
+}
+
+async fn send(ws: &mut actix_ws::Session, message: serde_json::Value) -> Result<(), actix_ws::Closed> {
+    ws.text(message.to_string()).await
+}
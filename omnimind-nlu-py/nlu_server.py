@@ -1,6 +1,7 @@
 # omnimind-nlu-py/nlu_server.py
 import sys
 import json
+from http.server import BaseHTTPRequestHandler, HTTPServer
 from transformers import pipeline
 
 # Load a zero-shot classification model once when the script starts.
@@ -14,14 +15,21 @@ classifier = None
 MODEL_NAME = "facebook/bart-large-mnli" 
 # Alternative: "facebook/bart-large-mnli" (larger, potentially more accurate)
 
+# Log lines go to stderr as "NLU_SERVER_LOG: <LEVEL> [<request id>] message";
+# omnimind-core logs them under the `nlu` target with that level and request ID.
+def log(message, level="INFO", request_id=None):
+    prefix = f"[{request_id}] " if request_id else ""
+    print(f"NLU_SERVER_LOG: {level} {prefix}{message}", file=sys.stderr)
+    sys.stderr.flush()
+
 def initialize_classifier():
     global classifier
     try:
-        print(f"NLU_SERVER_LOG: Initializing zero-shot classification pipeline with model: {MODEL_NAME}...", file=sys.stderr)
+        log(f"Initializing zero-shot classification pipeline with model: {MODEL_NAME}...")
         classifier = pipeline("zero-shot-classification", model=MODEL_NAME)
-        print("NLU_SERVER_LOG: Classifier initialized successfully.", file=sys.stderr)
+        log("Classifier initialized successfully.")
     except Exception as e:
-        print(f"NLU_SERVER_LOG: Error initializing classifier: {e}", file=sys.stderr)
+        log(f"Error initializing classifier: {e}", "ERROR")
         # Exit if classifier fails to load, as the script is useless without it.
         sys.exit(1)
 
@@ -49,7 +57,7 @@ label_to_command_keyword = {
     "terminate application": "quit"
 }
 
-def process_command(text_input):
+def process_command(text_input, request_id=None):
     if not classifier:
         return {"error": "Classifier not initialized."}
     
@@ -78,26 +86,88 @@ def process_command(text_input):
             "arguments_text": " ".join(text_input.split()[1:]) # Simple: take everything after first word as args
         }
     except Exception as e:
-        print(f"NLU_SERVER_LOG: Error processing command '{text_input}': {e}", file=sys.stderr)
+        log(f"Error processing command '{text_input}': {e}", "ERROR", request_id)
         return {"error": str(e), "original_text": text_input}
 
+class NluHttpHandler(BaseHTTPRequestHandler):
+    # POST {"text": "..."} -> the same JSON object the stdin/stdout mode prints.
+    # An X-Request-Id header tags the log lines written for the request.
+    def do_POST(self):
+        self.request_id = self.headers.get("X-Request-Id")
+        try:
+            length = int(self.headers.get("Content-Length", 0))
+            body = json.loads(self.rfile.read(length) or b"{}")
+            input_text = str(body.get("text", "")).strip()
+        except (ValueError, AttributeError) as e:
+            self.send_json(400, {"error": f"Invalid request body: {e}"})
+            return
+
+        if not input_text:
+            self.send_json(400, {"error": "Missing 'text' field."})
+            return
+
+        nlu_result = process_command(input_text, self.request_id)
+        self.send_json(500 if "error" in nlu_result else 200, nlu_result)
+
+    # GET -> {"status": "ok", "model": ...}: omnimind-core's readiness ping.
+    def do_GET(self):
+        self.request_id = self.headers.get("X-Request-Id")
+        self.send_json(200, {"status": "ok", "model": MODEL_NAME})
+
+    def send_json(self, status_code, payload):
+        encoded = json.dumps(payload).encode("utf-8")
+        self.send_response(status_code)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(encoded)))
+        self.end_headers()
+        self.wfile.write(encoded)
+
+    def log_message(self, format, *args):
+        log(f"{self.address_string()} {format % args}", request_id=getattr(self, "request_id", None))
+
+
+def serve_http(port):
+    server = HTTPServer(("0.0.0.0", port), NluHttpHandler)
+    log(f"Python NLU HTTP Server Ready on port {port}.")
+    server.serve_forever()
+
+
 if __name__ == "__main__":
     initialize_classifier()
-    
-    print("NLU_SERVER_LOG: Python NLU Server Ready. Waiting for input...", file=sys.stderr)
-    sys.stderr.flush()
+
+    # `python nlu_server.py --http [port]` serves POST requests instead of reading stdin.
+    if len(sys.argv) > 1 and sys.argv[1] == "--http":
+        serve_http(int(sys.argv[2]) if len(sys.argv) > 2 else 5005)
+        sys.exit(0)
+
+    log("Python NLU Server Ready. Waiting for input...")
 
     for line in sys.stdin:
         input_text = line.strip()
         
         if input_text.lower() == "__exit__": # Check for EXIT immediately
-            print("NLU_SERVER_LOG: Received __EXIT__ command. Shutting down.", file=sys.stderr)
-            sys.stderr.flush()
+            log("Received __EXIT__ command. Shutting down.")
             break # Exit the loop
 
         if not input_text: # Skip empty lines after checking for exit
             continue
 
-        nlu_result = process_command(input_text)
+        # omnimind-core sends {"text": ..., "request_id": ...}, or {"ping": true}
+        # to check that the model is loaded; plain text lines (typed by hand when
+        # testing) work too.
+        request_id = None
+        if input_text.startswith("{"):
+            try:
+                request = json.loads(input_text)
+                if request.get("ping"):
+                    print(json.dumps({"status": "ok", "model": MODEL_NAME}))
+                    sys.stdout.flush()
+                    continue
+                input_text = str(request.get("text", "")).strip()
+                request_id = request.get("request_id")
+            except (ValueError, AttributeError):
+                pass
+
+        nlu_result = process_command(input_text, request_id)
         print(json.dumps(nlu_result))
         sys.stdout.flush()
\ No newline at end of file